@@ -1,16 +1,223 @@
 extern crate ansi_term;
 extern crate clap;
 use super::common_structs::CommentType;
+use super::dates;
+use super::formats::csv::{Column, Dialect};
+use super::formats::grep::GrepStyle;
+use super::git::TreeSource;
 use ansi_term::Colour;
-use clap::{App, Arg};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 use std::{self, path::Path};
 
+/// Commands other than the default scan of a file or directory
+pub enum Subcommand {
+    /// Compare the special lines of two trees in the local git repository
+    Diff {
+        base: TreeSource,
+        head: TreeSource,
+        /// Set for `<base>...<head>`, where `head` is compared against its merge base with `base`
+        merge_base: bool,
+    },
+    /// Walk the git history to find when each special line was introduced and resolved
+    History { range: String },
+    /// Scan the git log messages of a revision range for special lines
+    Log { range: String },
+    /// Check the staged changes for forbidden tags, or install a git hook doing so
+    Hook {
+        action: HookAction,
+        forbidden: Vec<CommentType>,
+    },
+}
+
+pub enum HookAction {
+    /// Check the lines added by the staged changes
+    PreCommit,
+    /// Write a pre-commit hook into the local git repository
+    Install,
+}
+
+/// How the special lines of a scan are shown on stdout
+#[derive(Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Console,
+    Json,
+    /// One JSON object per line, written as soon as each file is scanned
+    Ndjson,
+    /// SARIF 2.1.0, for code scanning dashboards
+    Sarif,
+    /// A table with a header row, in the given dialect
+    Table(Dialect),
+    /// One `file:line:col:` line per special line, for editors
+    Grep(GrepStyle),
+    /// Checkstyle XML, for CI tools that collect static analysis warnings
+    Checkstyle,
+    /// JUnit XML with a failing test case per policy violation (e.g. a stale FIXME)
+    Junit,
+    /// GitHub Actions workflow commands, shown as annotations on the changed lines
+    Github,
+    /// GitLab Code Quality JSON, shown as inline findings in merge requests
+    Gitlab,
+    /// The Markdown checklist, as written by `--output-file`
+    Markdown,
+    /// A self-contained HTML page with search and filters
+    Html,
+    /// Org mode `* TODO` headings
+    Org,
+    /// An AsciiDoc checklist
+    AsciiDoc,
+    /// A reStructuredText list
+    Rst,
+    /// todo.txt tasks, one per line
+    TodoTxt,
+    /// A JSON array of tasks for Taskwarrior's `task import`
+    Taskwarrior,
+    /// The user-defined template given with `--template`
+    Template,
+}
+
+impl OutputFormat {
+    /// Names of the formats, as given to `--format`
+    pub const NAMES: [&'static str; 20] = [
+        "console",
+        "json",
+        "ndjson",
+        "sarif",
+        "csv",
+        "tsv",
+        "vimgrep",
+        "emacs",
+        "checkstyle",
+        "junit",
+        "github",
+        "gitlab",
+        "markdown",
+        "html",
+        "org",
+        "asciidoc",
+        "rst",
+        "todotxt",
+        "taskwarrior",
+        "template",
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "console" => Some(Self::Console),
+            "json" => Some(Self::Json),
+            "ndjson" => Some(Self::Ndjson),
+            "sarif" => Some(Self::Sarif),
+            "csv" => Some(Self::Table(Dialect::Csv)),
+            "tsv" => Some(Self::Table(Dialect::Tsv)),
+            "vimgrep" => Some(Self::Grep(GrepStyle::Vim)),
+            "emacs" => Some(Self::Grep(GrepStyle::Emacs)),
+            "checkstyle" => Some(Self::Checkstyle),
+            "junit" => Some(Self::Junit),
+            "github" => Some(Self::Github),
+            "gitlab" => Some(Self::Gitlab),
+            "markdown" => Some(Self::Markdown),
+            "html" => Some(Self::Html),
+            "org" => Some(Self::Org),
+            "asciidoc" => Some(Self::AsciiDoc),
+            "rst" => Some(Self::Rst),
+            "todotxt" => Some(Self::TodoTxt),
+            "taskwarrior" => Some(Self::Taskwarrior),
+            "template" => Some(Self::Template),
+            _ => None,
+        }
+    }
+
+    /// The document format of an `--output-file`, from its extension, Markdown if it's not known
+    pub fn from_extension(path: &str) -> Self {
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|x| x.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        match extension.as_str() {
+            "html" | "htm" => Self::Html,
+            "org" => Self::Org,
+            "adoc" | "asciidoc" => Self::AsciiDoc,
+            "rst" => Self::Rst,
+            _ => Self::Markdown,
+        }
+    }
+
+    /// Formats that point at a file and line, which the special lines of commit messages don't have
+    pub fn needs_file_location(&self) -> bool {
+        matches!(
+            self,
+            Self::Sarif
+                | Self::Grep(_)
+                | Self::Checkstyle
+                | Self::Junit
+                | Self::Github
+                | Self::Gitlab
+                | Self::TodoTxt
+                | Self::Taskwarrior
+        )
+    }
+
+    /// Formats with one line per special line, which can be written as soon as each file is scanned
+    pub fn is_line_based(&self) -> bool {
+        matches!(
+            self,
+            Self::Ndjson | Self::Grep(_) | Self::Github | Self::TodoTxt
+        )
+    }
+}
+
+/// An output format and where it's written to
+pub struct Output {
+    pub format: OutputFormat,
+    /// File the output is written to, stdout if `None`
+    pub path: Option<String>,
+}
+
+impl Output {
+    /// Parses a `--format` value, a format name optionally followed by `=<path>`
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let (name, path) = match value.split_once('=') {
+            Some((_, "")) => return Err(format!("no path given in `{}`", value)),
+            Some((name, path)) => (name, Some(path.to_string())),
+            None => (value, None),
+        };
+        match OutputFormat::from_name(name) {
+            Some(format) => Ok(Self { format, path }),
+            None => Err(format!(
+                "`{}` isn't a format, pick one of {}",
+                name,
+                OutputFormat::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
 pub struct CommandLineArgs {
     pub input_path: String,
     pub context: usize,
     pub display_type: CommentType,
+    /// Where the special lines are written to and in which formats, at most one of them on stdout
+    pub outputs: Vec<Output>,
+    /// Columns of the CSV and TSV output
+    pub columns: Vec<Column>,
+    /// Template file of the `template` format
+    pub template: Option<String>,
     pub output_filename: Option<String>,
-    pub markdown_output_flag: bool,
+    pub include_checked_tasks: bool,
+    pub blame: bool,
+    pub sort_by_age: bool,
+    /// Rank files and their special lines by breadcrumb count, churn and recency
+    pub rank_hotspot: bool,
+    /// Attach the `CODEOWNERS` owners to each special line and summarize them per owner
+    pub show_owners: bool,
+    /// Only keep the special lines owned by any of these owners
+    pub owner_filter: Vec<String>,
+    pub older_than_days: Option<i64>,
+    pub fail_on_stale: bool,
+    pub stale_age_days: i64,
+    /// Git tree to read the input from instead of the working directory, if any
+    pub tree_source: Option<TreeSource>,
+    pub subcommand: Option<Subcommand>,
 }
 
 impl CommandLineArgs {
@@ -26,7 +233,7 @@ impl CommandLineArgs {
                 .arg(
                     Arg::with_name("filename")
                     .help("Sets the input file to rustle")
-                    .required_unless("directory")
+                    .required_unless_one(&["directory", "rev", "staged"])
                 )
                 // Can be used in place of a file so that an entire directory tree can be processed recursively
                 .arg(
@@ -36,68 +243,345 @@ impl CommandLineArgs {
                     .long("dir")
                     .takes_value(true)
                 )
-                // Sets context lines opt
+                .arg(context_arg())
+                .args(&format_args())
+                .args(&shared_args())
+                // Reads the input from a git revision or the index instead of the working directory
                 .arg(
-                    Arg::with_name("context")
-                    .help("Tells rustler how many files of surrounding context to return for special lines")
+                    Arg::with_name("rev")
+                    .help("Scan the input as of this git revision (e.g. `HEAD~10`, a tag), without checking it out")
                     .required(false)
-                    .default_value("3")
-                    .long("ctx")
+                    .long("rev")
                     .takes_value(true)
+                    .conflicts_with("staged")
+                )
+                .arg(
+                    Arg::with_name("staged")
+                    .help("Scan the input as staged in the git index, i.e. exactly what would be committed")
+                    .required(false)
+                    .long("staged")
+                )
+                // Attributes each special line to its author using the local git repository
+                .arg(
+                    Arg::with_name("blame")
+                    .help("Show the author, commit and date of each special line (for files inside of a git repository)")
+                    .required(false)
+                    .long("blame")
                 )
-                // Sets the wanted type of display returned
+                // Sorts the special lines by how long ago they were introduced
                 .arg(
-                    Arg::with_name("type")
-                    .help("Selects what type of special lines get displayed [default: all]")
+                    Arg::with_name("sort")
+                    .help("Order of the special lines, `age` lists the oldest first (implies --blame) [default: file]")
                     .required(false)
-                    .possible_values(&["todo", "fixme", "note", "xxx", "all"])
-                    .long("type")
+                    .possible_values(&["file", "age"])
+                    .long("sort")
                     .takes_value(true)
                 )
-                // Handles setting the output filename (if one given)
+                // Ranks files by how worth addressing their special lines are
                 .arg(
-                    Arg::with_name("out")
-                    .help("If set, a Markdown version of the special lines will be written to this file")
+                    Arg::with_name("rank")
+                    .help("Rank files, and their special lines, by `hotspot`: breadcrumb count weighted by how often and how recently the file changed in git")
                     .required(false)
-                    .long("output-file")
-                    .require_equals(true)
+                    .possible_values(&["hotspot"])
+                    .long("rank")
                     .takes_value(true)
+                    .conflicts_with("sort")
+                )
+                // Attributes each special line to its owners from CODEOWNERS
+                .arg(
+                    Arg::with_name("owners")
+                    .help("Show the owners of each special line from the repository's CODEOWNERS file, with a summary per owner")
+                    .required(false)
+                    .long("owners")
                 )
+                .arg(
+                    Arg::with_name("owner")
+                    .help("Only show special lines owned by this owner, e.g. `@org/team` (can be repeated, implies --owners)")
+                    .required(false)
+                    .long("owner")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                )
+                // Only keeps special lines older than the given age
+                .arg(
+                    Arg::with_name("older-than")
+                    .help("Only show special lines introduced at least this long ago, e.g. `180d`, `6w`, `1y` (implies --blame)")
+                    .required(false)
+                    .long("older-than")
+                    .takes_value(true)
+                    .validator(validate_age)
+                )
+                // Exits with an error if there are FIXMEs older than the stale age
+                .arg(
+                    Arg::with_name("fail-on-stale")
+                    .help("Exit with a non-zero status if any FIXME is older than --stale-age (implies --blame)")
+                    .required(false)
+                    .long("fail-on-stale")
+                )
+                .arg(
+                    Arg::with_name("stale-age")
+                    .help("Age after which a FIXME counts as stale for --fail-on-stale")
+                    .required(false)
+                    .default_value("90d")
+                    .long("stale-age")
+                    .takes_value(true)
+                    .validator(validate_age)
+                )
+                // Compares the special lines between two revisions of the local git repository
+                .subcommand(
+                    SubCommand::with_name("diff")
+                    .about("Shows the special lines added, removed or moved to another file between two git revisions. A special line that only moved within its file counts as unchanged")
+                    .arg(
+                        Arg::with_name("range")
+                        .help("Revisions to compare, as `<base>..<head>`, or `<base>...<head>` to compare `<head>` against its merge base with `<base>` (`<base>` alone compares against HEAD)")
+                        .required_unless("staged")
+                    )
+                    .arg(
+                        Arg::with_name("staged")
+                        .help("Compare HEAD against the staged changes in the index")
+                        .long("staged")
+                        .conflicts_with("range")
+                    )
+                    .arg(context_arg())
+                    .args(&shared_args())
+                )
+                // Tracks when each special line was introduced and resolved
+                .subcommand(
+                    SubCommand::with_name("history")
+                    .about("Shows when (and by whom) each special line was introduced and resolved, with monthly stats")
+                    .arg(
+                        Arg::with_name("range")
+                        .help("Revision range of the git history to walk, e.g. `v1.0..HEAD`")
+                        .default_value("HEAD")
+                    )
+                    .args(&shared_args())
+                )
+                // Scans commit messages instead of files
+                .subcommand(
+                    SubCommand::with_name("log")
+                    .about("Shows the special lines left in the commit messages of the git log, with their commit")
+                    .arg(
+                        Arg::with_name("range")
+                        .help("Revision range of the git log to scan, e.g. `v1.0..HEAD`")
+                        .default_value("HEAD")
+                    )
+                    .arg(context_arg())
+                    .args(&format_args())
+                    .args(&shared_args())
+                )
+                // Checks staged changes before they get committed
+                .subcommand(
+                    SubCommand::with_name("hook")
+                    .about("Git hook mode, blocks commits that add forbidden tags or malformed TODOs")
+                    .setting(AppSettings::SubcommandRequiredElseHelp)
+                    .subcommand(
+                        SubCommand::with_name("pre-commit")
+                        .about("Checks the lines added by the staged changes, exits with a non-zero status on any problem")
+                        .arg(forbid_arg())
+                    )
+                    .subcommand(
+                        SubCommand::with_name("install")
+                        .about("Writes a pre-commit hook running `rustler hook pre-commit`, an existing hook is never overwritten")
+                        .arg(forbid_arg())
+                    )
+                )
+                .setting(AppSettings::SubcommandsNegateReqs)
                 .get_matches();
 
+        if let ("diff", Some(diff_matches)) = matches.subcommand() {
+            let (base, head, merge_base) = match diff_matches.value_of("range") {
+                Some(range) => parse_revision_range(range),
+                None => (
+                    TreeSource::Revision("HEAD".to_string()),
+                    TreeSource::Index,
+                    false,
+                ),
+            };
+            return CommandLineArgs::from_shared_args(
+                diff_matches,
+                ".".to_string(),
+                Some(Subcommand::Diff {
+                    base,
+                    head,
+                    merge_base,
+                }),
+            );
+        }
+
+        if let ("history", Some(history_matches)) = matches.subcommand() {
+            let range = history_matches.value_of("range").unwrap().to_string();
+            return CommandLineArgs::from_shared_args(
+                history_matches,
+                ".".to_string(),
+                Some(Subcommand::History { range }),
+            );
+        }
+
+        if let ("log", Some(log_matches)) = matches.subcommand() {
+            let range = log_matches.value_of("range").unwrap().to_string();
+            let args = CommandLineArgs::from_shared_args(
+                log_matches,
+                ".".to_string(),
+                Some(Subcommand::Log { range }),
+            );
+            if let Some(output) = args.outputs.iter().find(|x| x.format.needs_file_location()) {
+                let name = OutputFormat::NAMES
+                    .iter()
+                    .find(|x| OutputFormat::from_name(x) == Some(output.format))
+                    .unwrap_or(&"");
+                clap::Error::with_description(
+                    &format!(
+                        "--format {} points at files, which commit messages aren't, use `json`, `csv` or the terminal output for `log`",
+                        name
+                    ),
+                    clap::ErrorKind::InvalidValue,
+                )
+                .exit()
+            }
+            return args;
+        }
+
+        if let ("hook", Some(hook_matches)) = matches.subcommand() {
+            let (action, action_matches) = match hook_matches.subcommand() {
+                ("install", Some(install_matches)) => (HookAction::Install, install_matches),
+                (_, Some(pre_commit_matches)) => (HookAction::PreCommit, pre_commit_matches),
+                // a subcommand is required by clap
+                _ => unreachable!(),
+            };
+            let forbidden = action_matches
+                .values_of("forbid")
+                .unwrap()
+                .map(CommentType::get_display_type)
+                .collect();
+            return CommandLineArgs::from_shared_args(
+                action_matches,
+                ".".to_string(),
+                Some(Subcommand::Hook { action, forbidden }),
+            );
+        }
+
+        let tree_source = if let Some(revision) = matches.value_of("rev") {
+            Some(TreeSource::Revision(revision.to_string()))
+        } else if matches.is_present("staged") {
+            Some(TreeSource::Index)
+        } else {
+            None
+        };
+
         let input_path = {
             if let Some(filename) = matches.value_of("filename") {
                 filename.to_string()
+            } else if let Some(directory) = matches.value_of("directory") {
+                directory.to_string()
             } else {
-                matches.value_of("directory").unwrap().to_string()
+                ".".to_string()
             }
         };
 
-        if !Path::new(&input_path).exists() {
+        // paths in a git tree don't have to exist in the working directory
+        if tree_source.is_none() && !Path::new(&input_path).exists() {
             let usage = matches.usage.expect("Usage not generated by CLI app");
             let error_msg = make_error_msg("Input path not found!", &usage);
             let err = clap::Error::with_description(&error_msg, clap::ErrorKind::InvalidValue);
             err.exit();
         }
 
+        let mut args = CommandLineArgs::from_shared_args(&matches, input_path, None);
+
+        // age args are validated by clap, so parsing them can't fail here
+        let sort_by_age = matches.value_of("sort") == Some("age");
+        let older_than_days = matches
+            .value_of("older-than")
+            .map(|x| dates::parse_age(x).unwrap());
+        let fail_on_stale = matches.is_present("fail-on-stale");
+        let stale_age_days = dates::parse_age(matches.value_of("stale-age").unwrap()).unwrap();
+
+        // ages come from blame info, so any of the age args (and the JUnit policy report) turn it on
+        let blame = matches.is_present("blame")
+            || sort_by_age
+            || older_than_days.is_some()
+            || fail_on_stale
+            || args.outputs.iter().any(|x| x.format == OutputFormat::Junit);
+
+        args.blame = blame;
+        args.sort_by_age = sort_by_age;
+        args.rank_hotspot = matches.value_of("rank") == Some("hotspot");
+        args.owner_filter = matches
+            .values_of("owner")
+            .map_or(vec![], |x| x.map(|x| x.to_string()).collect());
+        args.show_owners = matches.is_present("owners") || !args.owner_filter.is_empty();
+        args.older_than_days = older_than_days;
+        args.fail_on_stale = fail_on_stale;
+        args.stale_age_days = stale_age_days;
+        args.tree_source = tree_source;
+        args
+    }
+
+    /// Reads the args shared between the scan and its subcommands, see [`shared_args`](shared_args)
+    fn from_shared_args(
+        matches: &ArgMatches,
+        input_path: String,
+        subcommand: Option<Subcommand>,
+    ) -> CommandLineArgs {
         // gets the type of the display wanted
         let display_type_arg = matches.value_of("type").unwrap_or("all").to_string();
         let display_type = CommentType::get_display_type(&display_type_arg);
 
         // sets output filename if one given
-        let markdown_output_flag;
-        let output_filename = {
-            match matches.value_of("out") {
-                Some(file_str) => {
-                    markdown_output_flag = true;
-                    Some(file_str.to_string())
-                }
-                None => {
-                    markdown_output_flag = false;
-                    None
-                }
-            }
+        let output_filename = matches.value_of("out").map(|x| x.to_string());
+
+        // formats are validated by clap, so parsing them can't fail here, the default
+        // console format is left to the check for stdout below
+        let mut outputs = match matches.values_of("format") {
+            Some(values) if matches.occurrences_of("format") > 0 => values
+                .map(|x| Output::parse(x).unwrap())
+                .collect::<Vec<Output>>(),
+            _ => vec![],
         };
+        if let Some(path) = &output_filename {
+            outputs.push(Output {
+                format: OutputFormat::from_extension(path),
+                path: Some(path.clone()),
+            });
+        }
+        // `--template` alone writes the template to stdout
+        let template = matches.value_of("template").map(|x| x.to_string());
+        let has_template_output = outputs.iter().any(|x| x.format == OutputFormat::Template);
+        if template.is_some() && !has_template_output {
+            outputs.push(Output {
+                format: OutputFormat::Template,
+                path: None,
+            });
+        } else if template.is_none() && has_template_output {
+            clap::Error::with_description(
+                "--format template needs a template file, given with --template <file>",
+                clap::ErrorKind::MissingRequiredArgument,
+            )
+            .exit()
+        }
+
+        // stdout shows the terminal output unless a format was picked for it
+        match outputs.iter().filter(|x| x.path.is_none()).count() {
+            0 => outputs.insert(
+                0,
+                Output {
+                    format: OutputFormat::Console,
+                    path: None,
+                },
+            ),
+            1 => {}
+            _ => clap::Error::with_description(
+                "Only one --format can be written to stdout, give the others a path (e.g. `--format sarif=report.sarif`)",
+                clap::ErrorKind::ArgumentConflict,
+            )
+            .exit(),
+        }
+
+        // only present where the format arg is
+        let columns = matches.values_of("columns").map_or(vec![], |x| {
+            x.filter_map(Column::from_name).collect::<Vec<Column>>()
+        });
 
         // context needs to be unwrapped from the cli then atoi'd into a usize
         let context: usize = matches.value_of("context").unwrap_or("0").parse().unwrap();
@@ -105,12 +589,128 @@ impl CommandLineArgs {
             input_path,
             context,
             display_type,
+            outputs,
+            columns,
+            template,
             output_filename,
-            markdown_output_flag,
+            include_checked_tasks: matches.is_present("include-checked"),
+            blame: false,
+            sort_by_age: false,
+            rank_hotspot: false,
+            show_owners: false,
+            owner_filter: vec![],
+            older_than_days: None,
+            fail_on_stale: false,
+            stale_age_days: 0,
+            tree_source: None,
+            subcommand,
         }
     }
 }
 
+/// Sets context lines opt
+fn context_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("context")
+        .help("Tells rustler how many files of surrounding context to return for special lines")
+        .required(false)
+        .default_value("3")
+        .long("ctx")
+        .takes_value(true)
+}
+
+/// Sets the output format of the special lines on stdout, and the columns of the tabular formats
+fn format_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("format")
+            .help("Output format, on stdout or written to a file with `format=path` (repeat it to write several): `json` follows the versioned schema in `schema/`, `ndjson` writes one of its breadcrumbs per line, `sarif` is SARIF 2.1.0, `csv` and `tsv` are tables with the --columns, `vimgrep` and `emacs` are `file:line:col:` lines for editors, `checkstyle` is Checkstyle XML, `junit` reports FIXMEs older than --stale-age as failing JUnit XML test cases (implies --blame), `github` writes GitHub Actions annotations, `gitlab` is a GitLab Code Quality report, `markdown` is the --output-file checklist, `html` a self-contained page and `org`, `asciidoc` and `rst` the checklist in those markups, `todotxt` writes todo.txt tasks and `taskwarrior` tasks for `task import`. The terminal output stays on stdout unless a format without a path is given")
+            .required(false)
+            .default_value("console")
+            .long("format")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .validator(validate_output),
+        Arg::with_name("columns")
+            .help("Comma separated columns of the `csv` and `tsv` formats, `context` holds the special line and its context")
+            .required(false)
+            .possible_values(&Column::NAMES)
+            .default_value("file,line,type,message,author,date,owner,issue")
+            .use_delimiter(true)
+            .long("columns")
+            .takes_value(true),
+        Arg::with_name("template")
+            .help("Writes the special lines with a template file instead of the terminal output (or as --format template=<path>): `{{ value | filter }}`, `{% for crumb in breadcrumbs %}` and `{% if crumb.metadata.blame %}`, see the README")
+            .required(false)
+            .long("template")
+            .takes_value(true),
+    ]
+}
+
+/// Args that control what gets scanned and how it's shown, for both the default scan and the subcommands
+fn shared_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        // Sets the wanted type of display returned
+        Arg::with_name("type")
+            .help("Selects what type of special lines get displayed [default: all]")
+            .required(false)
+            .possible_values(&["todo", "fixme", "note", "xxx", "all"])
+            .long("type")
+            .takes_value(true),
+        // Also reports checked task list items in Markdown and Org files
+        Arg::with_name("include-checked")
+            .help(
+                "Also report checked task list items (`- [x]`, `* DONE`) in Markdown and Org files",
+            )
+            .required(false)
+            .long("include-checked"),
+        // Handles setting the output filename (if one given)
+        Arg::with_name("out")
+            .help("If set, a Markdown version of the special lines will be written to this file, or in the format of its extension: `.html`, `.org`, `.adoc` or `.rst` (the same as `--format markdown=<file>` and so on)")
+            .required(false)
+            .long("output-file")
+            .require_equals(true)
+            .takes_value(true),
+    ]
+}
+
+/// Sets the tags the pre-commit hook rejects
+fn forbid_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("forbid")
+        .help("Comma separated tags that can't be added by a commit")
+        .required(false)
+        .possible_values(&["todo", "fixme", "note", "xxx"])
+        .default_value("fixme,xxx")
+        .use_delimiter(true)
+        .long("forbid")
+        .takes_value(true)
+}
+
+/// Splits `<base>..<head>` (or `<base>...<head>`, flagged by the returned `bool`) into its
+/// revisions, a lone `<base>` is compared against `HEAD`
+fn parse_revision_range(range: &str) -> (TreeSource, TreeSource, bool) {
+    let or_head = |x: &str| if x.is_empty() { "HEAD" } else { x }.to_string();
+    let (base, head, merge_base) = if let Some((base, head)) = range.split_once("...") {
+        (base, head, true)
+    } else if let Some((base, head)) = range.split_once("..") {
+        (base, head, false)
+    } else {
+        (range, "", false)
+    };
+    (
+        TreeSource::Revision(or_head(base)),
+        TreeSource::Revision(or_head(head)),
+        merge_base,
+    )
+}
+
+fn validate_age(age: String) -> Result<(), String> {
+    dates::parse_age(&age).map(|_| ())
+}
+
+fn validate_output(value: String) -> Result<(), String> {
+    Output::parse(&value).map(|_| ())
+}
+
 fn make_error_msg(message: &str, usage: &str) -> String {
     let red_error_str = Colour::Red.paint("ERROR".to_string()).to_string();
     let green_usage_str = Colour::White.paint(usage);
@@ -0,0 +1,226 @@
+use super::common_structs::MarkedSection;
+use super::git::{self, TreeSource};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Where a `CODEOWNERS` file is looked for, relative to the repository root, the first one found is used
+const CODEOWNERS_PATHS: [&str; 3] = [".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// Owners listed for sections whose file has no owners
+pub const UNOWNED: &str = "(unowned)";
+
+struct Rule {
+    pattern: String,
+    owners: Vec<String>,
+}
+
+/// The rules of a `CODEOWNERS` file
+pub struct CodeOwners {
+    rules: Vec<Rule>,
+}
+
+impl CodeOwners {
+    /// Parses the contents of a `CODEOWNERS` file, each line being a pattern followed by its owners
+    pub fn parse(contents: &str) -> Self {
+        let rules = contents
+            .lines()
+            .filter_map(|line| {
+                let line = line.split('#').next().unwrap_or_default();
+                let mut parts = line.split_whitespace();
+                let pattern = parts.next()?.to_string();
+                let owners = parts.map(|x| x.to_string()).collect();
+                Some(Rule { pattern, owners })
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// Owners of the file at `path` (relative to the repository root)
+    ///
+    /// The last matching rule wins, like on GitHub and GitLab, so a rule without owners
+    /// leaves the file unowned.
+    pub fn owners_of(&self, path: &str) -> &[String] {
+        self.rules
+            .iter()
+            .rev()
+            .find(|x| pattern_matches(&x.pattern, path))
+            .map_or(&[], |x| &x.owners)
+    }
+}
+
+/// Reads the `CODEOWNERS` file of the repository `directory` is in, from the root, `.github/`
+/// or `docs/`, as of `source` (or the working directory if `None`)
+pub fn find(directory: &Path, source: Option<&TreeSource>) -> io::Result<Option<CodeOwners>> {
+    let root = git::repo_root(directory)?;
+    let contents = match source {
+        Some(source) => {
+            let paths = CODEOWNERS_PATHS
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<String>>();
+            git::read_tree_files(&root, source, &paths)?
+                .into_iter()
+                .next()
+                .map(|x| x.lines.join("\n"))
+        }
+        None => CODEOWNERS_PATHS
+            .iter()
+            .find_map(|x| fs::read_to_string(root.join(x)).ok()),
+    };
+    Ok(contents.map(|x| CodeOwners::parse(&x)))
+}
+
+/// Attaches the owners from `code_owners` to each of the sections
+///
+/// `root` is the root of the repository, and `source` the tree the sections were read
+/// from, `None` for the working directory.
+pub fn attach_owners(
+    sections: &mut [MarkedSection],
+    code_owners: &CodeOwners,
+    root: &Path,
+    source: Option<&TreeSource>,
+) {
+    for section in sections.iter_mut() {
+        if let Some(path) = git::repo_relative_path(&section.title_xxx, root, source) {
+            section.owners = code_owners.owners_of(&path).to_vec();
+        }
+    }
+}
+
+/// Only keeps the sections owned by any of `owners`, compared case insensitively
+pub fn retain_owned_by(sections: &mut Vec<MarkedSection>, owners: &[String]) {
+    sections.retain(|section| {
+        section
+            .owners
+            .iter()
+            .any(|x| owners.iter().any(|owner| owner.eq_ignore_ascii_case(x)))
+    });
+}
+
+/// Number of sections per owner, a section with several owners counts for each of them
+///
+/// Sections without owners are counted under [`UNOWNED`](UNOWNED).
+pub fn summarize<'a>(sections: impl Iterator<Item = &'a MarkedSection>) -> BTreeMap<String, usize> {
+    let mut summary = BTreeMap::new();
+    for section in sections {
+        if section.owners.is_empty() {
+            *summary.entry(UNOWNED.to_string()).or_default() += 1;
+        }
+        for owner in &section.owners {
+            *summary.entry(owner.clone()).or_default() += 1;
+        }
+    }
+    summary
+}
+
+/// Matches `path` against a `CODEOWNERS` pattern, which follows `.gitignore` rules
+///
+/// Patterns without a `/` (other than a trailing one) match at any depth, the rest are
+/// relative to the repository root. A pattern matching a directory matches everything in it.
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let is_dir_only = pattern.ends_with('/');
+    let pattern = pattern.trim_end_matches('/');
+    let pattern = if pattern.contains('/') {
+        pattern.trim_start_matches('/').to_string()
+    } else {
+        format!("**/{}", pattern)
+    };
+
+    // `docs/*` only matches the files directly in `docs/`, not the ones in its subdirectories
+    let in_dir = !pattern.ends_with("/*")
+        && glob_matches(format!("{}/**", pattern).as_bytes(), path.as_bytes());
+    in_dir || (!is_dir_only && glob_matches(pattern.as_bytes(), path.as_bytes()))
+}
+
+/// Glob matching where `*` and `?` stay within a path segment and `**` spans any number of them
+fn glob_matches(pattern: &[u8], path: &[u8]) -> bool {
+    if pattern == b"**" {
+        return true;
+    }
+    if let Some(rest) = pattern.strip_prefix(b"**/") {
+        return glob_matches(rest, path)
+            || path
+                .iter()
+                .enumerate()
+                .any(|(i, x)| *x == b'/' && glob_matches(rest, &path[i + 1..]));
+    }
+    match pattern.first() {
+        None => path.is_empty(),
+        Some(b'*') => {
+            let segment_len = path.iter().position(|x| *x == b'/').unwrap_or(path.len());
+            (0..=segment_len).any(|i| glob_matches(&pattern[1..], &path[i..]))
+        }
+        Some(b'?') => match path.first() {
+            Some(x) if *x != b'/' => glob_matches(&pattern[1..], &path[1..]),
+            _ => false,
+        },
+        Some(x) => path.first() == Some(x) && glob_matches(&pattern[1..], &path[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_repo;
+
+    #[test]
+    fn patterns_follow_gitignore_rules() {
+        assert!(pattern_matches("*", "src/main.rs"));
+        assert!(pattern_matches("*.rs", "src/main.rs"));
+        assert!(!pattern_matches("*.rs", "src/main.py"));
+        assert!(pattern_matches("/docs/", "docs/guide/intro.md"));
+        assert!(!pattern_matches("/docs/", "src/docs/intro.md"));
+        assert!(pattern_matches("docs/", "src/docs/intro.md"));
+        assert!(pattern_matches("src/*.rs", "src/main.rs"));
+        assert!(!pattern_matches("src/*.rs", "src/cli/args.rs"));
+        assert!(pattern_matches("src/**/args.rs", "src/cli/args.rs"));
+        assert!(pattern_matches("/build/logs", "build/logs/today.log"));
+        assert!(pattern_matches("docs/*", "docs/intro.md"));
+        assert!(!pattern_matches("docs/*", "docs/guide/intro.md"));
+        assert!(pattern_matches("README.m?", "README.md"));
+        assert!(!pattern_matches("/README.md", "docs/README.md"));
+    }
+
+    #[test]
+    fn last_matching_rule_wins() {
+        let code_owners = CodeOwners::parse(
+            "# default owners\n\
+             *       @org/core\n\
+             \n\
+             /docs/  @org/docs @alice  # writers\n\
+             /docs/generated/\n",
+        );
+
+        assert_eq!(code_owners.owners_of("src/main.rs"), ["@org/core"]);
+        assert_eq!(
+            code_owners.owners_of("docs/intro.md"),
+            ["@org/docs", "@alice"]
+        );
+        assert!(code_owners.owners_of("docs/generated/api.md").is_empty());
+    }
+
+    #[test]
+    fn codeowners_found_in_github_dir() {
+        let repo = test_repo::init();
+        let dir = repo.path();
+        fs::create_dir(dir.join(".github")).unwrap();
+        test_repo::commit_file(
+            dir,
+            ".github/CODEOWNERS",
+            "*.rs @org/rust\n",
+            "Alice",
+            "2020-01-10T00:00:00Z",
+        );
+
+        let code_owners = find(dir, None).unwrap().unwrap();
+        assert_eq!(code_owners.owners_of("a.rs"), ["@org/rust"]);
+
+        fs::write(dir.join(".github/CODEOWNERS"), "*.rs @org/other\n").unwrap();
+        let committed = find(dir, Some(&TreeSource::Revision("HEAD".to_string())))
+            .unwrap()
+            .unwrap();
+        assert_eq!(committed.owners_of("a.rs"), ["@org/rust"]);
+    }
+}
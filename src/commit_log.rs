@@ -0,0 +1,199 @@
+use super::common_structs::{BlameInfo, CommentType, Line, MarkedSection, ScanOptions};
+use super::git;
+use super::language::Language;
+use std::cmp;
+use std::io;
+use std::path::Path;
+
+/// Separates the commits in the `git log` output, the fields of each commit are `%x1f` separated
+const RECORD_SEPARATOR: char = '\x1e';
+
+/// Scans the messages of the commits in `range` of the repository `directory` is in for special lines
+///
+/// # Arguments
+///
+/// * `range` - Revision range to scan, as understood by `git log` (e.g. `v1.0..HEAD`)
+///
+/// # Returns
+///
+/// * `Vec<MarkedSection>` - One section per special line, newest commit first. The title of
+///   each section is the commit SHA, its line numbers are relative to the commit message and
+///   its blame info is the commit itself.
+///
+/// # Notes
+///
+/// Commit messages are prose, so a line only counts if it has an uppercase tag as a whole word
+/// (`FIXME: follow-up`, not `fixme`), the same as Markdown cells of notebooks.
+pub fn scan_commit_messages(
+    directory: &Path,
+    range: &str,
+    options: &ScanOptions,
+) -> io::Result<Vec<MarkedSection>> {
+    let output = git::run_git(
+        directory,
+        &[
+            "log",
+            "--no-color",
+            "--format=%H%x1f%an%x1f%ae%x1f%at%x1f%B%x1e",
+            range,
+            "--",
+        ],
+    )?;
+    Ok(parse_log(&output)
+        .into_iter()
+        .flat_map(|(commit, message)| unpack_message(commit, &message, options).into_iter())
+        .collect())
+}
+
+/// Splits the `git log` output into each commit and the lines of its message
+fn parse_log(output: &str) -> Vec<(BlameInfo, Vec<String>)> {
+    output
+        .split(RECORD_SEPARATOR)
+        .filter_map(|record| {
+            let fields = record
+                .trim_start_matches('\n')
+                .splitn(5, '\x1f')
+                .collect::<Vec<&str>>();
+            if fields.len() != 5 {
+                return None;
+            }
+            let commit = BlameInfo {
+                commit: fields[0].to_string(),
+                author: fields[1].to_string(),
+                email: fields[2].to_string(),
+                timestamp: fields[3].parse().unwrap_or(0),
+            };
+            let message = fields[4]
+                .trim_end()
+                .lines()
+                .map(|x| x.to_string())
+                .collect();
+            Some((commit, message))
+        })
+        .collect()
+}
+
+fn unpack_message(
+    commit: BlameInfo,
+    message: &[String],
+    options: &ScanOptions,
+) -> Vec<MarkedSection> {
+    let mut marked_sections = Vec::new();
+
+    for (i, line) in message.iter().enumerate() {
+        let comment_type = match CommentType::marked_type(line) {
+            Some(comment_type) => comment_type,
+            None => continue,
+        };
+
+        let range_start = i.saturating_sub(options.context);
+        let range_end = cmp::min(message.len(), i + options.context + 1);
+        let lines = message
+            .iter()
+            .enumerate()
+            .take(range_end)
+            .skip(range_start)
+            .map(|(j, x)| Line::new(x, j + 1, j == i))
+            .collect();
+
+        marked_sections.push(MarkedSection {
+            lines,
+            comment_type,
+            title_xxx: commit.commit.clone(),
+            language: Language::Unknown,
+            embedded: false,
+            cell: None,
+            blame: Some(commit.clone()),
+            owners: vec![],
+        });
+    }
+    marked_sections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_repo;
+
+    #[test]
+    fn only_uppercase_tags_count_in_messages() {
+        let commit = BlameInfo {
+            commit: "0123456789abcdef".to_string(),
+            author: "Alice".to_string(),
+            email: "alice@example.com".to_string(),
+            timestamp: 0,
+        };
+        let message = [
+            "Add flag",
+            "",
+            "FIXME: follow-up to remove todo list",
+            "fixme later",
+        ]
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<String>>();
+        let options = ScanOptions {
+            context: 1,
+            ..ScanOptions::default()
+        };
+
+        let sections = unpack_message(commit, &message, &options);
+
+        assert_eq!(sections.len(), 1);
+        assert!(sections[0].comment_type == CommentType::Fixme);
+        assert_eq!(sections[0].location(), "0123456789abcdef:3");
+        assert_eq!(
+            sections[0]
+                .lines
+                .iter()
+                .map(|x| x.number)
+                .collect::<Vec<usize>>(),
+            vec![2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn commit_messages_scanned_over_range() {
+        let repo = test_repo::init();
+        let dir = repo.path();
+        test_repo::commit_file_with_message(
+            dir,
+            "a.rs",
+            "fn a() {}\n",
+            "Alice",
+            "2020-01-10T00:00:00Z",
+            "Add a\n\nTODO: document a",
+        );
+        test_repo::commit_file_with_message(
+            dir,
+            "b.rs",
+            "fn b() {}\n",
+            "Bob",
+            "2020-02-10T00:00:00Z",
+            "Add b\n\nFIXME: follow-up to remove b\nNOTE: b is temporary",
+        );
+
+        let sections = scan_commit_messages(dir, "HEAD", &ScanOptions::default()).unwrap();
+        let summary = sections
+            .iter()
+            .map(|x| {
+                (
+                    x.comment_type.to_string(),
+                    x.blame.as_ref().unwrap().author.clone(),
+                    x.line_number(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                ("Fixme".to_string(), "Bob".to_string(), 3),
+                ("Note".to_string(), "Bob".to_string(), 4),
+                ("Todo".to_string(), "Alice".to_string(), 3),
+            ]
+        );
+
+        let sections = scan_commit_messages(dir, "HEAD~1..HEAD", &ScanOptions::default()).unwrap();
+        assert_eq!(sections.len(), 2);
+    }
+}
@@ -0,0 +1,28 @@
+use crate::dates;
+
+/// Who last touched a line, according to `git blame`
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlameInfo {
+    pub author: String,
+    pub email: String,
+    pub commit: String,
+    /// Author time of the commit, as a unix timestamp
+    pub timestamp: i64,
+}
+
+impl BlameInfo {
+    /// Abbreviated commit SHA, as shown by `git log --oneline`
+    pub fn short_commit(&self) -> &str {
+        &self.commit[..self.commit.len().min(7)]
+    }
+
+    /// Commit date formatted as `YYYY-MM-DD`
+    pub fn date(&self) -> String {
+        dates::format_date(self.timestamp)
+    }
+
+    /// Days since the line was committed, relative to `now` (a unix timestamp)
+    pub fn age_days(&self, now: i64) -> i64 {
+        dates::days_since(self.timestamp, now)
+    }
+}
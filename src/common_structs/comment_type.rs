@@ -2,6 +2,7 @@ use std::fmt;
 
 /// Represents the different types of breadcrumb comments that
 /// can be detected by the parser.
+#[allow(clippy::upper_case_acronyms)]
 #[derive(PartialEq, Clone)]
 pub enum CommentType {
     Todo,
@@ -55,11 +56,55 @@ impl CommentType {
         }
     }
 
-    pub fn check_line_special(line: &str) -> bool {
-        let is_special = Self::get_special_line_type(line) != Self::Other;
-        let is_comment = is_comment(line);
+    /// Checks if special lines of this type are shown for the `--type` arg's `display_type`,
+    /// where [`CommentType::Other`](Self::Other) shows all of them
+    pub fn is_displayed(&self, display_type: &CommentType) -> bool {
+        *display_type == Self::Other || self == display_type
+    }
+
+    /// The tag of the type as written in code (e.g. `FIXME`), `None` for [`CommentType::Other`](Self::Other)
+    pub fn tag(&self) -> Option<&'static str> {
+        match self {
+            Self::Todo => Some("TODO"),
+            Self::Fixme => Some("FIXME"),
+            Self::Note => Some("NOTE"),
+            Self::XXX => Some("XXX"),
+            Self::Other => None,
+        }
+    }
+
+    /// Byte offset of the first place the type's tag is written as a whole word in `line`,
+    /// matched case insensitively, so `Mastodon` or `todos` don't count as a `TODO`
+    pub fn whole_word_position(&self, line: &str) -> Option<usize> {
+        let tag = self.tag()?.to_ascii_lowercase();
+        let line = line.to_ascii_lowercase();
+        let is_word_char = |x: Option<char>| x.is_some_and(|x| x.is_alphanumeric());
+        line.match_indices(&tag).map(|(i, _)| i).find(|i| {
+            !is_word_char(line[..*i].chars().next_back())
+                && !is_word_char(line[i + tag.len()..].chars().next())
+        })
+    }
+
+    /// The first tag written as a whole word in `line`, with its byte offset, see
+    /// [`whole_word_position`](Self::whole_word_position)
+    pub fn find_tag(line: &str) -> Option<(Self, usize)> {
+        [Self::Todo, Self::Fixme, Self::Note, Self::XXX]
+            .iter()
+            .filter_map(|x| x.whole_word_position(line).map(|start| (x.clone(), start)))
+            .min_by_key(|x| x.1)
+    }
 
-        is_special && is_comment
+    /// The type of the first uppercase tag (e.g. `TODO:`) written as a whole word in the line,
+    /// used to find task markers in prose where there are no comments to look for
+    pub fn marked_type(line: &str) -> Option<Self> {
+        line.split(|x: char| !x.is_alphanumeric())
+            .find_map(|word| match word {
+                "TODO" => Some(Self::Todo),
+                "FIXME" => Some(Self::Fixme),
+                "NOTE" => Some(Self::Note),
+                "XXX" => Some(Self::XXX),
+                _ => None,
+            })
     }
 }
 
@@ -76,8 +121,3 @@ impl fmt::Display for CommentType {
         Ok(())
     }
 }
-
-fn is_comment(line: &str) -> bool {
-    const COMMENT_SYMBOLS: [&str; 5] = ["//", "/***", "#", r#"""""#, "/**"];
-    COMMENT_SYMBOLS.iter().map(|x| line.contains(x)).any(|x| x)
-}
@@ -1,58 +1,103 @@
-use super::{CommentType, Line, ParseData, ParsedDirectory, ParsedFile};
+use super::{BlameInfo, CommentType, Line, ParseData, ParsedDirectory, ParsedFile, ScanOptions};
+use crate::language::{self, Language};
+use crate::task_list;
 use std::cmp;
 
+/// Endings of block comments, trimmed off the message of a special line
+const COMMENT_CLOSERS: [&str; 6] = ["--}}", "*/", "-->", "#}", "%>", "}}"];
+
 pub struct MarkedSection {
     pub lines: Vec<Line>,
     pub comment_type: CommentType,
     pub title_xxx: String,
+    /// Language of the region the special line was found in
+    pub language: Language,
+    /// Set if `language` is embedded in a file of another language (e.g. a `<script>` in a `.vue` file)
+    pub embedded: bool,
+    /// 1-based notebook cell the section was found in, line numbers are then relative to the cell
+    pub cell: Option<usize>,
+    /// Who last touched the special line, only set when blame info is requested
+    pub blame: Option<BlameInfo>,
+    /// Owners of the file from `CODEOWNERS`, only set when ownership is requested
+    pub owners: Vec<String>,
 }
 
 impl MarkedSection {
-    fn unpack_lines_for_directory(dir_data: ParsedDirectory, context: usize) -> Vec<Self> {
-        dir_data
-            .files
+    fn unpack_lines_for_directory(dir_data: ParsedDirectory, options: &ScanOptions) -> Vec<Self> {
+        Self::unpack_lines_for_files(dir_data.files, options)
+    }
+
+    fn unpack_lines_for_files(files: Vec<ParsedFile>, options: &ScanOptions) -> Vec<Self> {
+        files
             .into_iter()
-            .map(|x| Self::unpack_lines_for_file(x, context).into_iter())
-            .flatten()
+            .flat_map(|x| Self::unpack_lines_for_file(x, options).into_iter())
             .collect()
     }
 
-    fn unpack_lines_for_file(file_data: ParsedFile, context: usize) -> Vec<Self> {
+    fn unpack_lines_for_file(file_data: ParsedFile, options: &ScanOptions) -> Vec<Self> {
+        let context = options.context;
         let lines = file_data.lines;
+        let host_language = file_data.language;
+        let regions = language::classify_lines(host_language, &lines);
+        // notebook markdown cells are prose, so task markers count even outside of comments
+        let is_prose = file_data.cell.is_some() && host_language == Language::Markdown;
         let mut marked_sections = Vec::new();
 
         let lines_len = lines.len();
-        let mut i = 0;
 
-        while i < lines_len {
-            if CommentType::check_line_special(&lines[i]) {
-                let mut current_lines = Vec::new();
+        for (i, region) in regions.iter().enumerate() {
+            // prose has no comments, the type comes from the marker that was found instead
+            let marked_type = if is_prose && !region.is_comment {
+                CommentType::marked_type(&lines[i])
+            } else {
+                None
+            };
+            let is_marked = region.is_comment || marked_type.is_some();
+            let mut comment_type =
+                marked_type.unwrap_or_else(|| CommentType::get_special_line_type(&lines[i]));
+            let is_task = !region.is_comment
+                && task_list::is_task_item(
+                    &lines[i],
+                    region.language,
+                    options.include_checked_tasks,
+                );
 
-                // add context # of lines behind
-                let range_start = i.checked_sub(context).unwrap_or(0);
-                for j in range_start..i {
-                    current_lines.push(Line::new(&lines[j], j, false));
+            if is_task {
+                // task items don't need a tag of their own, but keep it if there is one
+                if comment_type == CommentType::Other {
+                    comment_type = CommentType::Todo;
                 }
+            } else if !is_marked || comment_type == CommentType::Other {
+                continue;
+            }
 
-                // add special line
-                current_lines.push(Line::new(&lines[i], i, true));
+            let mut current_lines = Vec::new();
 
-                // add context # of line ahead
-                let range_end = cmp::min(lines_len, i + context + 1);
-                for j in (i + 1)..range_end {
-                    current_lines.push(Line::new(&lines[j], j, false));
-                }
+            // add context # of lines behind
+            let range_start = i.saturating_sub(context);
+            for (j, line) in lines.iter().enumerate().take(i).skip(range_start) {
+                current_lines.push(Line::new(line, j + 1, false));
+            }
 
-                // get type of special line
-                let comment_type = CommentType::get_special_line_type(&lines[i]);
+            // add special line
+            current_lines.push(Line::new(&lines[i], i + 1, true));
 
-                marked_sections.push(Self {
-                    lines: current_lines,
-                    title_xxx: file_data.filename.clone(),
-                    comment_type,
-                });
+            // add context # of line ahead
+            let range_end = cmp::min(lines_len, i + context + 1);
+            for (j, line) in lines.iter().enumerate().take(range_end).skip(i + 1) {
+                current_lines.push(Line::new(line, j + 1, false));
             }
-            i += 1;
+
+            marked_sections.push(Self {
+                lines: current_lines,
+                title_xxx: file_data.filename.clone(),
+                comment_type,
+                language: region.language,
+                embedded: region.language != host_language,
+                cell: file_data.cell,
+                blame: None,
+                owners: vec![],
+            });
         }
         marked_sections
     }
@@ -61,9 +106,9 @@ impl MarkedSection {
     ///
     /// # Arguments
     ///
-    /// * `parsed_data` - The file data for a single file or a directory
+    /// * `parsed_data` - The file data for a single file, a notebook or a directory
     ///
-    /// * `context` - The amount of context lines surrounding the special lines
+    /// * `options` - The amount of context lines surrounding the special lines, and which task items to include
     ///
     /// # Returns
     ///
@@ -71,11 +116,93 @@ impl MarkedSection {
     ///
     /// # Notes
     ///
-    /// If `context` passed in is > `lines.len()` then it will count context until EOF.
-    pub fn unpack_lines(parsed_data: ParseData, context: usize) -> Vec<Self> {
+    /// If `context` passed in is > `lines.len()` then it will count context until EOF
+    /// (or the end of the cell for notebooks).
+    ///
+    /// The comment syntax used to find special lines follows the language of the
+    /// region each line is in, see [`classify_lines`](language::classify_lines). Task list
+    /// items in Markdown and Org files are picked up as `TODO`s, see [`is_task_item`](task_list::is_task_item).
+    pub fn unpack_lines(parsed_data: ParseData, options: &ScanOptions) -> Vec<Self> {
         match parsed_data {
-            ParseData::Directory(dir_data) => Self::unpack_lines_for_directory(dir_data, context),
-            ParseData::File(file_data) => Self::unpack_lines_for_file(file_data, context),
+            ParseData::Directory(dir_data) => Self::unpack_lines_for_directory(dir_data, options),
+            ParseData::File(file_data) => Self::unpack_lines_for_file(file_data, options),
+            ParseData::Notebook(notebook) => Self::unpack_lines_for_files(notebook.cells, options),
+        }
+    }
+
+    /// Days since the special line was introduced, if blame info is attached
+    pub fn age_days(&self, now: i64) -> Option<i64> {
+        self.blame.as_ref().map(|x| x.age_days(now))
+    }
+
+    /// The special line itself, without its context
+    pub fn special_line(&self) -> Option<&Line> {
+        self.lines.iter().find(|x| x.is_special)
+    }
+
+    /// 1-based line number of the special line
+    pub fn line_number(&self) -> usize {
+        self.special_line().map_or(0, |x| x.number)
+    }
+
+    /// 1-based column of the tag in the special line, or of its first non-blank character
+    /// for task items without a tag
+    pub fn column(&self) -> usize {
+        let content = self.special_line().map_or("", |x| x.content.as_str());
+        let start = match self.tag_position(content) {
+            Some(start) => start,
+            None => content.len() - content.trim_start().len(),
+        };
+        content[..start].chars().count() + 1
+    }
+
+    /// Text of the special line after its tag, e.g. `fix this` for `// TODO(alice): fix this */`
+    ///
+    /// Task items without a tag keep the text after their checkbox (or heading keyword) as
+    /// the message, e.g. `write docs` for `- [ ] write docs`.
+    pub fn message(&self) -> String {
+        let content = self.special_line().map_or("", |x| x.content.as_str());
+        let start = match (self.tag_position(content), self.comment_type.tag()) {
+            (Some(start), Some(tag)) => start + tag.len(),
+            _ => {
+                let text = task_list::item_text(content).unwrap_or(content);
+                return text.trim().to_string();
+            }
+        };
+
+        let mut message = &content[start..];
+        if message.starts_with('(') {
+            if let Some(end) = message.find(')') {
+                message = &message[end + 1..];
+            }
+        }
+        let mut message = message.trim_start_matches(|x: char| x == ':' || x.is_whitespace());
+        for closer in COMMENT_CLOSERS.iter() {
+            message = message.trim_end().trim_end_matches(closer);
+        }
+        message.trim().to_string()
+    }
+
+    /// Byte offset of the section's tag in `content`, matched case insensitively
+    ///
+    /// Whole words are preferred, so the tag of `// notebook NOTE: x` is the second one.
+    fn tag_position(&self, content: &str) -> Option<usize> {
+        let tag = self.comment_type.tag()?.to_ascii_lowercase();
+        self.comment_type
+            .whole_word_position(content)
+            .or_else(|| content.to_ascii_lowercase().find(&tag))
+    }
+
+    /// Position of the special line, as `file:line` or `notebook.ipynb#cell=7:line=3` for notebook cells
+    pub fn location(&self) -> String {
+        match self.cell {
+            Some(cell) => format!(
+                "{}#cell={}:line={}",
+                self.title_xxx,
+                cell,
+                self.line_number()
+            ),
+            None => format!("{}:{}", self.title_xxx, self.line_number()),
         }
     }
 }
@@ -1,13 +1,17 @@
+mod blame_info;
 mod comment_type;
 mod lines;
 mod marked_section;
 mod output_block;
 mod parsed_data;
+mod scan_options;
 mod tokenized_line;
 
+pub use blame_info::BlameInfo;
 pub use comment_type::CommentType;
 pub use lines::Line;
 pub use marked_section::MarkedSection;
 pub use output_block::OutputBlock;
-pub use parsed_data::{ParseData, ParsedDirectory, ParsedFile};
+pub use parsed_data::{ParseData, ParsedDirectory, ParsedFile, ParsedNotebook};
+pub use scan_options::ScanOptions;
 pub use tokenized_line::TokenizedLine;
@@ -1,32 +1,46 @@
-use super::{CommentType, MarkedSection, TokenizedLine};
+use super::{BlameInfo, CommentType, MarkedSection, TokenizedLine};
+use crate::language::Language;
 
 pub struct OutputBlock {
+    /// Path of the file, or SHA of the commit, the block is from
+    pub file: String,
     pub block_type: CommentType,
+    pub language: Language,
+    pub embedded: bool,
+    pub cell: Option<usize>,
+    pub blame: Option<BlameInfo>,
+    pub owners: Vec<String>,
     pub special_line: TokenizedLine,
     pub all_lines: Vec<TokenizedLine>,
 }
 
 impl OutputBlock {
     /// Process a single [`MarkedSection`](MarkedSection) into an [`OutputBlock`](Self)
-    pub fn from_marked_section(marked_section: MarkedSection) -> Self {
+    pub fn from_marked_section(marked_section: &MarkedSection) -> Self {
         let mut special_line = None;
         let mut all_lines = Vec::new();
 
-        for line in marked_section.lines {
+        for line in &marked_section.lines {
             if line.is_special {
                 special_line = Some(TokenizedLine::from_code_line(
                     line.content.clone(),
                     line.number,
                 ));
             }
-            let output_line = TokenizedLine::from_code_line(line.content, line.number);
+            let output_line = TokenizedLine::from_code_line(line.content.clone(), line.number);
             all_lines.push(output_line);
         }
 
         Self {
             // should never be none
             special_line: special_line.unwrap(),
-            block_type: marked_section.comment_type,
+            file: marked_section.title_xxx.clone(),
+            block_type: marked_section.comment_type.clone(),
+            language: marked_section.language,
+            embedded: marked_section.embedded,
+            cell: marked_section.cell,
+            blame: marked_section.blame.clone(),
+            owners: marked_section.owners.clone(),
             all_lines,
         }
     }
@@ -1,14 +1,36 @@
+use crate::language::Language;
+
 pub struct ParsedDirectory {
-    pub directory_path: String,
     pub files: Vec<ParsedFile>,
 }
 
 pub struct ParsedFile {
     pub filename: String,
     pub lines: Vec<String>,
+    pub language: Language,
+    /// 1-based index of the notebook cell the lines were read from, if any
+    pub cell: Option<usize>,
+}
+
+impl ParsedFile {
+    /// Creates a [`ParsedFile`](Self) for a plain source file, with the language taken from its extension
+    pub fn new(filename: String, lines: Vec<String>) -> Self {
+        Self {
+            language: Language::from_path(&filename),
+            filename,
+            lines,
+            cell: None,
+        }
+    }
+}
+
+/// All of the scannable cells of a single Jupyter notebook
+pub struct ParsedNotebook {
+    pub cells: Vec<ParsedFile>,
 }
 
 pub enum ParseData {
     Directory(ParsedDirectory),
     File(ParsedFile),
+    Notebook(ParsedNotebook),
 }
@@ -0,0 +1,8 @@
+/// Options that control which lines get picked up as special lines, and how much context they get
+#[derive(Clone, Copy, Default)]
+pub struct ScanOptions {
+    /// The amount of context lines surrounding the special lines
+    pub context: usize,
+    /// If set, checked task list items (`- [x] ...`, `* DONE ...`) are reported along with the unchecked ones
+    pub include_checked_tasks: bool,
+}
@@ -1,9 +1,6 @@
-use super::CommentType;
-
 pub struct TokenizedLine {
     pub line_number: usize,
     pub tokenized_line: Vec<String>,
-    pub is_special: bool,
 }
 
 impl TokenizedLine {
@@ -16,7 +13,6 @@ impl TokenizedLine {
         Self {
             line_number,
             tokenized_line,
-            is_special: CommentType::check_line_special(&line),
         }
     }
 }
@@ -0,0 +1,140 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Current time as a unix timestamp
+pub fn now_timestamp() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |x| x.as_secs() as i64)
+}
+
+/// Whole days between `timestamp` and `now`, timestamps in the future count as 0 days
+pub fn days_since(timestamp: i64, now: i64) -> i64 {
+    (now - timestamp).max(0) / SECONDS_PER_DAY
+}
+
+/// Parses an age like `180d`, `6w`, `3m` or `1y` into a number of days
+///
+/// A month counts as 30 days and a year as 365. A plain number is read as days.
+pub fn parse_age(age: &str) -> Result<i64, String> {
+    let age = age.trim();
+    let (number, multiplier) = match age.chars().last() {
+        Some('d') => (&age[..age.len() - 1], 1),
+        Some('w') => (&age[..age.len() - 1], 7),
+        Some('m') => (&age[..age.len() - 1], 30),
+        Some('y') => (&age[..age.len() - 1], 365),
+        _ => (age, 1),
+    };
+    number
+        .parse::<i64>()
+        .ok()
+        .filter(|x| *x >= 0)
+        .and_then(|x| x.checked_mul(multiplier))
+        .ok_or_else(|| {
+            format!(
+                "invalid age `{}`, expected e.g. `180d`, `6w`, `3m` or `1y`",
+                age
+            )
+        })
+}
+
+/// Converts a unix timestamp (in seconds) into a `(year, month, day)` UTC date
+///
+/// Uses the days-to-civil algorithm from <http://howardhinnant.github.io/date_algorithms.html>
+pub fn civil_from_timestamp(timestamp: i64) -> (i64, u32, u32) {
+    let days = timestamp.div_euclid(SECONDS_PER_DAY);
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Formats a unix timestamp as an ISO 8601 date (`YYYY-MM-DD`)
+pub fn format_date(timestamp: i64) -> String {
+    let (year, month, day) = civil_from_timestamp(timestamp);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Finds the first `YYYY-MM-DD` date written in `text`, e.g. the deadline of `TODO(2025-03-01)`
+pub fn find_date(text: &str) -> Option<(i64, u32, u32)> {
+    let bytes = text.as_bytes();
+    let is_digit = |i: usize| bytes.get(i).is_some_and(|x| x.is_ascii_digit());
+    (0..bytes.len()).find_map(|start| {
+        let is_date = (start == 0 || !is_digit(start - 1))
+            && !is_digit(start + 10)
+            && (0..10).all(|i| match i {
+                4 | 7 => bytes.get(start + i) == Some(&b'-'),
+                _ => is_digit(start + i),
+            });
+        if !is_date {
+            return None;
+        }
+        let year = text[start..start + 4].parse().ok()?;
+        let month = text[start + 5..start + 7].parse().ok()?;
+        let day = text[start + 8..start + 10].parse().ok()?;
+        ((1..=12).contains(&month) && (1..=31).contains(&day)).then_some((year, month, day))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ages_parsed_into_days() {
+        assert_eq!(parse_age("180d"), Ok(180));
+        assert_eq!(parse_age("2w"), Ok(14));
+        assert_eq!(parse_age("3m"), Ok(90));
+        assert_eq!(parse_age("1y"), Ok(365));
+        assert_eq!(parse_age("45"), Ok(45));
+    }
+
+    #[test]
+    fn invalid_ages_return_error() {
+        for age in &["", "d", "-3d", "ten days", "5h", "99999999999999999y"] {
+            assert!(parse_age(age).is_err(), "{}", age);
+        }
+    }
+
+    #[test]
+    fn days_since_rounds_down_and_clamps_future() {
+        assert_eq!(days_since(0, SECONDS_PER_DAY * 2 - 1), 1);
+        assert_eq!(days_since(SECONDS_PER_DAY, 0), 0);
+    }
+
+    #[test]
+    fn first_valid_date_found() {
+        assert_eq!(
+            find_date("// TODO(2025-03-01): ship it"),
+            Some((2025, 3, 1))
+        );
+        assert_eq!(
+            find_date("TODO: v12025-03-01, not 2025-13-01 but 2026-01-31"),
+            Some((2026, 1, 31))
+        );
+        assert_eq!(find_date("TODO: 2025-3-1"), None);
+    }
+
+    #[test]
+    fn epoch_formatted_correctly() {
+        assert_eq!(format_date(0), "1970-01-01");
+    }
+
+    #[test]
+    fn leap_day_formatted_correctly() {
+        assert_eq!(format_date(1_709_208_000), "2024-02-29");
+    }
+
+    #[test]
+    fn dates_before_epoch_formatted_correctly() {
+        assert_eq!(format_date(-86_400), "1969-12-31");
+    }
+}
@@ -0,0 +1,218 @@
+use super::common_structs::{MarkedSection, ScanOptions};
+use super::git::{self, TreeSource};
+use super::parser;
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+/// A special line that exists in both trees, but in a different file
+pub struct MovedSection {
+    /// Location of the special line in the base tree
+    pub from: String,
+    /// The special line as found in the head tree
+    pub section: MarkedSection,
+}
+
+/// Special lines that differ between two trees of a repository
+pub struct DiffReport {
+    pub added: Vec<MarkedSection>,
+    pub removed: Vec<MarkedSection>,
+    pub moved: Vec<MovedSection>,
+}
+
+/// Compares the special lines of the `base` and `head` trees of the repository `directory` is in
+///
+/// Only the files that changed between the two trees get read, straight out of the
+/// git object database, so neither tree needs to be checked out.
+pub fn diff_trees(
+    directory: &Path,
+    base: &TreeSource,
+    head: &TreeSource,
+    options: &ScanOptions,
+) -> io::Result<DiffReport> {
+    let root = git::repo_root(directory)?;
+    let paths = git::changed_files(&root, base, head)?;
+
+    let base_data = parser::read_data_for_tree(&root, base, &paths)?;
+    let head_data = parser::read_data_for_tree(&root, head, &paths)?;
+
+    Ok(compare(
+        MarkedSection::unpack_lines(base_data, options),
+        MarkedSection::unpack_lines(head_data, options),
+    ))
+}
+
+/// Matches up the special lines of two trees by their type and text
+///
+/// A special line counts as:
+///
+/// * unchanged if the same text is in the same file in both trees (even if its line number changed)
+/// * moved if the same text is only found in another file
+/// * added or removed if there is no match in the other tree at all
+pub fn compare(base: Vec<MarkedSection>, head: Vec<MarkedSection>) -> DiffReport {
+    let mut base: Vec<Option<MarkedSection>> = base.into_iter().map(Some).collect();
+    let mut unmatched_by_key: HashMap<(String, String), Vec<usize>> = HashMap::new();
+    for (i, section) in base.iter().enumerate() {
+        let section = section.as_ref().unwrap();
+        unmatched_by_key
+            .entry(match_key(section))
+            .or_default()
+            .push(i);
+    }
+
+    // first pass drops the special lines that stayed in the same file
+    let mut unmatched_head = vec![];
+    for section in head {
+        let candidates = unmatched_by_key.entry(match_key(&section)).or_default();
+        let same_file = candidates
+            .iter()
+            .position(|x| base[*x].as_ref().unwrap().title_xxx == section.title_xxx);
+        match same_file {
+            Some(position) => {
+                base[candidates.remove(position)] = None;
+            }
+            None => unmatched_head.push(section),
+        }
+    }
+
+    // anything left with a match in another file got moved
+    let mut added = vec![];
+    let mut moved = vec![];
+    for section in unmatched_head {
+        let candidates = unmatched_by_key.entry(match_key(&section)).or_default();
+        if candidates.is_empty() {
+            added.push(section);
+        } else {
+            let from = base[candidates.remove(0)].take().unwrap();
+            moved.push(MovedSection {
+                from: from.location(),
+                section,
+            });
+        }
+    }
+
+    DiffReport {
+        added,
+        removed: base.into_iter().flatten().collect(),
+        moved,
+    }
+}
+
+fn match_key(section: &MarkedSection) -> (String, String) {
+    let special_line = section
+        .lines
+        .iter()
+        .find(|x| x.is_special)
+        .map_or("", |x| x.content.trim());
+    (section.comment_type.to_string(), special_line.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common_structs::{CommentType, Line};
+    use crate::git::test_repo;
+    use crate::language::Language;
+
+    fn section(filename: &str, line_number: usize, content: &str) -> MarkedSection {
+        MarkedSection {
+            lines: vec![Line::new(content, line_number, true)],
+            comment_type: CommentType::get_special_line_type(content),
+            title_xxx: filename.to_string(),
+            language: Language::Rust,
+            embedded: false,
+            cell: None,
+            blame: None,
+            owners: vec![],
+        }
+    }
+
+    fn locations(sections: &[MarkedSection]) -> Vec<String> {
+        sections.iter().map(|x| x.location()).collect()
+    }
+
+    #[test]
+    fn shifted_lines_in_same_file_are_unchanged() {
+        let report = compare(
+            vec![section("a.rs", 1, "// TODO: one")],
+            vec![section("a.rs", 5, "  // TODO: one")],
+        );
+        assert!(report.added.is_empty() && report.removed.is_empty() && report.moved.is_empty());
+    }
+
+    #[test]
+    fn new_and_deleted_lines_are_added_and_removed() {
+        let report = compare(
+            vec![section("a.rs", 1, "// TODO: old")],
+            vec![section("a.rs", 1, "// TODO: new")],
+        );
+        assert_eq!(locations(&report.added), vec!["a.rs:1"]);
+        assert_eq!(locations(&report.removed), vec!["a.rs:1"]);
+        assert!(report.moved.is_empty());
+    }
+
+    #[test]
+    fn lines_in_another_file_are_moved() {
+        let report = compare(
+            vec![section("a.rs", 3, "// FIXME: shared")],
+            vec![section("b.rs", 7, "// FIXME: shared")],
+        );
+        assert!(report.added.is_empty() && report.removed.is_empty());
+        assert_eq!(report.moved.len(), 1);
+        assert_eq!(report.moved[0].from, "a.rs:3");
+        assert_eq!(report.moved[0].section.location(), "b.rs:7");
+    }
+
+    #[test]
+    fn duplicate_lines_matched_one_to_one() {
+        let report = compare(
+            vec![section("a.rs", 1, "// TODO: dup")],
+            vec![
+                section("a.rs", 1, "// TODO: dup"),
+                section("a.rs", 9, "// TODO: dup"),
+            ],
+        );
+        assert_eq!(locations(&report.added), vec!["a.rs:9"]);
+    }
+
+    #[test]
+    fn revisions_compared_from_object_database() {
+        let repo = test_repo::init();
+        let dir = repo.path();
+        test_repo::commit_file(
+            dir,
+            "a.rs",
+            "// TODO: keep\n// FIXME: go\n",
+            "Alice",
+            "2020-01-01T00:00:00Z",
+        );
+        test_repo::commit_file(
+            dir,
+            "b.rs",
+            "// NOTE: moves\n",
+            "Alice",
+            "2020-01-01T00:00:00Z",
+        );
+        test_repo::commit_file(
+            dir,
+            "a.rs",
+            "fn main() {}\n// TODO: keep\n// NOTE: moves\n// XXX: new\n",
+            "Bob",
+            "2020-02-01T00:00:00Z",
+        );
+        test_repo::commit_file(dir, "b.rs", "\n", "Bob", "2020-02-01T00:00:00Z");
+        // the working tree is irrelevant to the comparison
+        std::fs::write(dir.join("a.rs"), "// TODO: dirty\n").unwrap();
+
+        let options = ScanOptions::default();
+        let base = TreeSource::Revision("HEAD~2".to_string());
+        let head = TreeSource::Revision("HEAD".to_string());
+        let report = diff_trees(dir, &base, &head, &options).expect("diff should succeed");
+
+        assert_eq!(locations(&report.added), vec!["a.rs:4"]);
+        assert_eq!(locations(&report.removed), vec!["a.rs:2"]);
+        assert_eq!(report.moved.len(), 1);
+        assert_eq!(report.moved[0].from, "b.rs:1");
+        assert_eq!(report.moved[0].section.location(), "a.rs:3");
+    }
+}
@@ -1,22 +1,21 @@
-use super::common_structs::{MarkedSection, OutputBlock};
+use super::diff::DiffReport;
+use super::history::HistoryReport;
 use super::output_formatter;
 use std::fs;
 use std::io;
 
-/// top-level function for outputting to a markdown file
-pub fn export_marked_sections_to_markdown_file(
-    marked_sections: Vec<MarkedSection>,
-    file_extension: Option<&str>,
-    filename: &String,
-) -> io::Result<()> {
-    let markdown_output_str = output_formatter::get_markdown_output_str(
-        marked_sections
-            .into_iter()
-            .map(|x| OutputBlock::from_marked_section(x))
-            .collect::<Vec<OutputBlock>>(),
-        file_extension,
-    );
+/// Writes the added, removed and moved special lines of a diff to a markdown file
+pub fn export_diff_to_markdown_file(report: DiffReport, filename: &str) -> io::Result<()> {
+    let markdown_output_str = output_formatter::get_markdown_diff_str(report);
 
-    fs::write(filename, &markdown_output_str.as_bytes())?;
+    fs::write(filename, markdown_output_str.as_bytes())?;
+    Ok(())
+}
+
+/// Writes the lifecycle of each special line, and the monthly stats, to a markdown file
+pub fn export_history_to_markdown_file(report: HistoryReport, filename: &str) -> io::Result<()> {
+    let markdown_output_str = output_formatter::get_markdown_history_str(report);
+
+    fs::write(filename, markdown_output_str.as_bytes())?;
     Ok(())
 }
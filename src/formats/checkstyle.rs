@@ -0,0 +1,84 @@
+use super::xml;
+use crate::common_structs::{CommentType, MarkedSection};
+use crate::render::{Renderer, Report};
+use std::io::{self, Write};
+
+/// Checkstyle severity of a type, the same mapping as the SARIF levels
+fn severity(comment_type: &CommentType) -> &'static str {
+    match comment_type {
+        CommentType::XXX => "error",
+        CommentType::Fixme => "warning",
+        _ => "info",
+    }
+}
+
+/// Renders the special lines as a Checkstyle XML report, with an `<error>` per special line
+/// grouped under a `<file>` per file, in the order the files were scanned
+pub fn render<'a>(sections: impl Iterator<Item = &'a MarkedSection>) -> String {
+    let mut files: Vec<(&str, Vec<&MarkedSection>)> = vec![];
+    for section in sections {
+        match files.iter_mut().find(|x| x.0 == section.title_xxx) {
+            Some((_, file_sections)) => file_sections.push(section),
+            None => files.push((&section.title_xxx, vec![section])),
+        }
+    }
+
+    let mut output = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    output.push_str("<checkstyle version=\"8.0\">\n");
+    for (filename, file_sections) in files {
+        output.push_str(&format!("  <file name=\"{}\">\n", xml::escape(filename)));
+        for section in file_sections {
+            let tag = section.comment_type.tag().unwrap_or_default();
+            output.push_str(&format!(
+                "    <error line=\"{}\" column=\"{}\" severity=\"{}\" message=\"{}\" source=\"rustler.{}\"/>\n",
+                section.line_number(),
+                section.column(),
+                severity(&section.comment_type),
+                xml::escape(format!("{} {}", tag, section.message()).trim_end()),
+                tag
+            ));
+        }
+        output.push_str("  </file>\n");
+    }
+    output.push_str("</checkstyle>\n");
+    output
+}
+
+/// The Checkstyle report of the displayed special lines
+pub struct Checkstyle;
+
+impl Renderer for Checkstyle {
+    fn render(&self, report: &Report, out: &mut dyn Write) -> io::Result<()> {
+        write!(out, "{}", render(report.displayed()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::fixtures::section;
+
+    #[test]
+    fn errors_grouped_per_file() {
+        let mut other_file = section();
+        other_file.title_xxx = "src/<lib>.rs".to_string();
+        other_file.comment_type = CommentType::XXX;
+        other_file.lines[1].content = "// XXX: a & b".to_string();
+
+        let output = render([section(), other_file, section()].iter());
+
+        assert_eq!(
+            output,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <checkstyle version=\"8.0\">\n  \
+               <file name=\"src/main.rs\">\n    \
+                 <error line=\"5\" column=\"8\" severity=\"info\" message=\"TODO handle errors\" source=\"rustler.TODO\"/>\n    \
+                 <error line=\"5\" column=\"8\" severity=\"info\" message=\"TODO handle errors\" source=\"rustler.TODO\"/>\n  \
+               </file>\n  \
+               <file name=\"src/&lt;lib&gt;.rs\">\n    \
+                 <error line=\"5\" column=\"4\" severity=\"error\" message=\"XXX a &amp; b\" source=\"rustler.XXX\"/>\n  \
+               </file>\n\
+             </checkstyle>\n"
+        );
+    }
+}
@@ -0,0 +1,239 @@
+use crate::common_structs::MarkedSection;
+use crate::render::{Renderer, Report};
+use std::io::{self, Write};
+
+/// Columns of the CSV and TSV exports, in the order given with `--columns`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Column {
+    File,
+    Line,
+    Type,
+    Message,
+    Author,
+    Date,
+    Owner,
+    Issue,
+    /// The special line and its context lines, collapsed into a single cell
+    Context,
+}
+
+impl Column {
+    /// Names accepted by `--columns`
+    pub const NAMES: [&'static str; 9] = [
+        "file", "line", "type", "message", "author", "date", "owner", "issue", "context",
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "file" => Some(Self::File),
+            "line" => Some(Self::Line),
+            "type" => Some(Self::Type),
+            "message" => Some(Self::Message),
+            "author" => Some(Self::Author),
+            "date" => Some(Self::Date),
+            "owner" => Some(Self::Owner),
+            "issue" => Some(Self::Issue),
+            "context" => Some(Self::Context),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAMES[*self as usize]
+    }
+
+    fn value(&self, section: &MarkedSection) -> String {
+        match self {
+            Self::File => section.title_xxx.clone(),
+            Self::Line => section.line_number().to_string(),
+            Self::Type => section.comment_type.tag().unwrap_or_default().to_string(),
+            Self::Message => section.message(),
+            Self::Author => section
+                .blame
+                .as_ref()
+                .map_or(String::new(), |x| x.author.clone()),
+            Self::Date => section.blame.as_ref().map_or(String::new(), |x| x.date()),
+            Self::Owner => section.owners.join(" "),
+            Self::Issue => section
+                .special_line()
+                .and_then(|x| issue_reference(&x.content))
+                .unwrap_or_default(),
+            Self::Context => section
+                .lines
+                .iter()
+                .map(|x| x.content.as_str())
+                .collect::<Vec<&str>>()
+                .join("\n"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Dialect {
+    /// Comma separated, quoted as in RFC 4180
+    Csv,
+    /// Tab separated, with tabs, newlines and backslashes escaped as `\t`, `\n` and `\\`
+    Tsv,
+}
+
+impl Dialect {
+    fn escape(&self, field: &str) -> String {
+        match self {
+            Self::Csv => {
+                if field.contains([',', '"', '\n', '\r']) {
+                    format!("\"{}\"", field.replace('"', "\"\""))
+                } else {
+                    field.to_string()
+                }
+            }
+            Self::Tsv => field
+                .replace('\\', "\\\\")
+                .replace('\t', "\\t")
+                .replace('\n', "\\n")
+                .replace('\r', "\\r"),
+        }
+    }
+
+    fn separator(&self) -> &'static str {
+        match self {
+            Self::Csv => ",",
+            Self::Tsv => "\t",
+        }
+    }
+}
+
+/// Renders the special lines as a table with a header row, one row per special line
+pub fn render<'a>(
+    sections: impl Iterator<Item = &'a MarkedSection>,
+    columns: &[Column],
+    dialect: Dialect,
+) -> String {
+    let row = |fields: Vec<String>| {
+        let fields = fields
+            .iter()
+            .map(|x| dialect.escape(x))
+            .collect::<Vec<String>>();
+        format!("{}\n", fields.join(dialect.separator()))
+    };
+
+    let mut output = row(columns.iter().map(|x| x.name().to_string()).collect());
+    for section in sections {
+        output.push_str(&row(columns.iter().map(|x| x.value(section)).collect()));
+    }
+    output
+}
+
+/// Finds an issue reference in a line: an issue URL, a tracker key like `PROJ-123` or a `#123`
+pub fn issue_reference(line: &str) -> Option<String> {
+    let words = line
+        .split(|x: char| x.is_whitespace() || matches!(x, '(' | ')' | '[' | ']' | ',' | ';'))
+        .map(|x| x.trim_end_matches(['.', ':']))
+        .collect::<Vec<&str>>();
+
+    let is_url = |x: &&str| {
+        (x.starts_with("https://") || x.starts_with("http://"))
+            && ["/issues/", "/pull/", "/browse/", "/-/issues/"]
+                .iter()
+                .any(|path| x.contains(path))
+    };
+    let is_tracker_key = |x: &&str| match x.split_once('-') {
+        Some((project, number)) => {
+            project.len() > 1
+                && project.starts_with(|x: char| x.is_ascii_uppercase())
+                && project
+                    .chars()
+                    .all(|x| x.is_ascii_uppercase() || x.is_ascii_digit())
+                && is_number(number)
+        }
+        None => false,
+    };
+    let is_hash_number = |x: &&str| x.strip_prefix('#').is_some_and(is_number);
+
+    words
+        .iter()
+        .copied()
+        .find(is_url)
+        .or_else(|| words.iter().copied().find(is_tracker_key))
+        .or_else(|| words.iter().copied().find(is_hash_number))
+        .map(|x| x.to_string())
+}
+
+fn is_number(x: &str) -> bool {
+    !x.is_empty() && x.chars().all(|x| x.is_ascii_digit())
+}
+
+/// A table of the displayed special lines
+pub struct Table {
+    pub columns: Vec<Column>,
+    pub dialect: Dialect,
+}
+
+impl Renderer for Table {
+    fn render(&self, report: &Report, out: &mut dyn Write) -> io::Result<()> {
+        write!(
+            out,
+            "{}",
+            render(report.displayed(), &self.columns, self.dialect)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::fixtures::section;
+
+    #[test]
+    fn issue_references_found() {
+        assert_eq!(issue_reference("// TODO(#42): x"), Some("#42".to_string()));
+        assert_eq!(
+            issue_reference("// FIXME: see PROJ-1234."),
+            Some("PROJ-1234".to_string())
+        );
+        assert_eq!(
+            issue_reference("# TODO: https://github.com/org/repo/issues/7 and #8"),
+            Some("https://github.com/org/repo/issues/7".to_string())
+        );
+        assert_eq!(issue_reference("// TODO: port to C# 10"), None);
+        assert_eq!(issue_reference("// TODO: fix #hashtag"), None);
+    }
+
+    #[test]
+    fn csv_fields_quoted() {
+        let mut quoted = section();
+        quoted.lines[1].content = "// TODO: say \"hi\", then #12".to_string();
+        let columns = [
+            Column::File,
+            Column::Line,
+            Column::Type,
+            Column::Message,
+            Column::Author,
+            Column::Date,
+            Column::Owner,
+            Column::Issue,
+        ];
+
+        let output = render([quoted].iter(), &columns, Dialect::Csv);
+
+        assert_eq!(
+            output,
+            "file,line,type,message,author,date,owner,issue\n\
+             src/main.rs,5,TODO,\"say \"\"hi\"\", then #12\",Alice,2020-09-13,@org/core,#12\n"
+        );
+    }
+
+    #[test]
+    fn context_collapsed_into_one_cell() {
+        let sections = [section()];
+        let columns = [Column::Line, Column::Context];
+
+        assert_eq!(
+            render(sections.iter(), &columns, Dialect::Csv),
+            "line,context\n5,\"fn main() {\n    /* TODO(alice): handle errors */\n}\"\n"
+        );
+        assert_eq!(
+            render(sections.iter(), &columns, Dialect::Tsv),
+            "line\tcontext\n5\tfn main() {\\n    /* TODO(alice): handle errors */\\n}\n"
+        );
+    }
+}
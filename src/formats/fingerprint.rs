@@ -0,0 +1,120 @@
+use super::relative_path;
+use crate::common_structs::MarkedSection;
+use crate::git::TreeSource;
+use std::collections::HashMap;
+use std::fs;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// 64-bit FNV-1a hash, which (unlike `std`'s hashers) is the same across runs, platforms and Rust versions
+pub fn fnv1a_64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(FNV_OFFSET_BASIS, |hash, x| {
+        (hash ^ u64::from(*x)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Stable fingerprints of the special lines, as 16 hex digits each
+///
+/// A fingerprint is made of the file (relative to its repository, see [`repo_path`](repo_path)), the tag and the trimmed text of the special line, so it
+/// survives lines being added or removed above it and only changes when the line itself
+/// does. Identical lines in the same file are told apart by their order.
+///
+/// `source` is the tree the files were read from, `None` for the working directory.
+pub fn fingerprints<'a>(
+    sections: impl Iterator<Item = &'a MarkedSection>,
+    source: Option<&TreeSource>,
+) -> Vec<String> {
+    let mut occurrences: HashMap<u64, usize> = HashMap::new();
+    sections
+        .map(|section| {
+            let key = format!(
+                "{}\0{}\0{}",
+                repo_path(&section.title_xxx, source),
+                section.comment_type.tag().unwrap_or_default(),
+                section.special_line().map_or("", |x| x.content.trim())
+            );
+            let hash = fnv1a_64(key.as_bytes());
+            let occurrence = occurrences.entry(hash).or_default();
+            *occurrence += 1;
+            format!(
+                "{:016x}",
+                fnv1a_64(format!("{:016x}:{}", hash, occurrence).as_bytes())
+            )
+        })
+        .collect()
+}
+
+/// Path of a file relative to the root of the git repository it's in, so a file gets the same
+/// fingerprint whichever directory the scan was run from
+///
+/// Files read from a git tree (`source` isn't `None`), whose path is already relative to the
+/// root, and files that aren't in a repository keep their own path, without a leading `./`.
+pub fn repo_path(path: &str, source: Option<&TreeSource>) -> String {
+    let on_disk = source
+        .is_none()
+        .then(|| fs::canonicalize(path).ok())
+        .flatten();
+    let repo_relative = on_disk.and_then(|absolute| {
+        let root = absolute
+            .ancestors()
+            .skip(1)
+            .find(|x| x.join(".git").exists())?;
+        let relative = absolute.strip_prefix(root).ok()?;
+        Some(
+            relative
+                .components()
+                .map(|x| x.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+        )
+    });
+    repo_relative.unwrap_or_else(|| relative_path(&path.replace('\\', "/")).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common_structs::Line;
+    use crate::formats::fixtures::section;
+
+    #[test]
+    fn fnv1a_matches_reference_values() {
+        assert_eq!(fnv1a_64(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a_64(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a_64(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn fingerprints_ignore_line_numbers_but_not_duplicates() {
+        let first = section();
+        let mut moved = section();
+        moved.lines = vec![Line::new("    /* TODO(alice): handle errors */", 40, true)];
+        let mut edited = section();
+        edited.lines[1].content = "    /* TODO(alice): handle all errors */".to_string();
+
+        let moved_fingerprints = fingerprints([moved].iter(), None);
+        let all_fingerprints = fingerprints([section(), first, edited].iter(), None);
+
+        assert_eq!(moved_fingerprints[0], all_fingerprints[0]);
+        assert_ne!(all_fingerprints[0], all_fingerprints[1]);
+        assert_ne!(all_fingerprints[0], all_fingerprints[2]);
+        assert_eq!(all_fingerprints[0].len(), 16);
+    }
+
+    #[test]
+    fn paths_relative_to_repository_root() {
+        let repo = crate::git::test_repo::init();
+        fs::create_dir(repo.path().join("src")).unwrap();
+        fs::write(repo.path().join("src/lib.rs"), "// TODO: x\n").unwrap();
+        let absolute = repo.path().join("src/../src/lib.rs");
+
+        assert_eq!(repo_path(absolute.to_str().unwrap(), None), "src/lib.rs");
+        assert_eq!(repo_path("./not/on/disk.rs", None), "not/on/disk.rs");
+
+        // tree paths are never resolved against the working directory
+        let index = TreeSource::Index;
+        let tree_path = absolute.to_str().unwrap().replace('\\', "/");
+        assert_eq!(repo_path(&tree_path, Some(&index)), tree_path);
+    }
+}
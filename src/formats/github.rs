@@ -0,0 +1,72 @@
+use crate::common_structs::{CommentType, MarkedSection};
+use crate::render::{self, Renderer, Report};
+use std::io::{self, Write};
+
+/// Workflow command an annotation is made with, the same mapping as the SARIF levels
+fn command(comment_type: &CommentType) -> &'static str {
+    match comment_type {
+        CommentType::XXX => "error",
+        CommentType::Fixme => "warning",
+        _ => "notice",
+    }
+}
+
+/// Escapes the message of a workflow command
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escapes a `key=value` property of a workflow command, which can't hold `:` or `,` either
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// A special line as a GitHub Actions workflow command, shown as an annotation on its line
+/// (e.g. `::warning file=src/main.rs,line=5,col=8,title=FIXME::leaks`)
+pub fn line(section: &MarkedSection) -> String {
+    let tag = section.comment_type.tag().unwrap_or_default();
+    let message = section.message();
+    format!(
+        "::{} file={},line={},col={},title={}::{}",
+        command(&section.comment_type),
+        escape_property(super::relative_path(&section.title_xxx)),
+        section.line_number(),
+        section.column(),
+        tag,
+        escape_data(if message.is_empty() { tag } else { &message })
+    )
+}
+
+/// A workflow command per displayed special line
+pub struct Github;
+
+impl Renderer for Github {
+    fn render(&self, report: &Report, out: &mut dyn Write) -> io::Result<()> {
+        render::write_lines(out, report.displayed().map(line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::fixtures::section;
+
+    #[test]
+    fn annotations_escaped() {
+        let mut fixme = section();
+        fixme.comment_type = CommentType::Fixme;
+        fixme.title_xxx = "./src/a,b.rs".to_string();
+        fixme.lines[1].content = "// FIXME: 100% broken: a, b".to_string();
+
+        assert_eq!(
+            line(&section()),
+            "::notice file=src/main.rs,line=5,col=8,title=TODO::handle errors"
+        );
+        assert_eq!(
+            line(&fixme),
+            "::warning file=src/a%2Cb.rs,line=5,col=4,title=FIXME::100%25 broken: a, b"
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use super::fingerprint;
+use crate::common_structs::{CommentType, MarkedSection};
+use crate::git::TreeSource;
+use crate::render::{Renderer, Report};
+use serde_json::{json, Value};
+use std::io::{self, Write};
+
+/// Code Quality severity of a type, the same order as the SARIF levels
+fn severity(comment_type: &CommentType) -> &'static str {
+    match comment_type {
+        CommentType::XXX => "critical",
+        CommentType::Fixme => "major",
+        CommentType::Todo => "minor",
+        CommentType::Note | CommentType::Other => "info",
+    }
+}
+
+/// Builds a GitLab Code Quality report, an array with an issue per special line
+///
+/// Fingerprints are the same as the SARIF ones, so GitLab can tell which breadcrumbs a merge
+/// request added or resolved.
+pub fn report<'a>(
+    sections: impl Iterator<Item = &'a MarkedSection> + Clone,
+    source: Option<&TreeSource>,
+) -> Value {
+    let issues = sections
+        .clone()
+        .zip(fingerprint::fingerprints(sections, source))
+        .map(|(section, fingerprint)| {
+            let tag = section.comment_type.tag().unwrap_or_default();
+            let message = section.message();
+            json!({
+                "type": "issue",
+                "check_name": format!("rustler/{}", tag),
+                "description": if message.is_empty() {
+                    tag.to_string()
+                } else {
+                    format!("{}: {}", tag, message)
+                },
+                "categories": ["Style"],
+                "severity": severity(&section.comment_type),
+                "fingerprint": fingerprint,
+                "location": {
+                    "path": super::relative_path(&section.title_xxx),
+                    "lines": { "begin": section.line_number() },
+                },
+            })
+        })
+        .collect::<Vec<Value>>();
+    Value::Array(issues)
+}
+
+/// The Code Quality report of the displayed special lines, pretty printed
+pub struct Gitlab;
+
+impl Renderer for Gitlab {
+    fn render(&self, scan: &Report, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "{:#}", report(scan.displayed(), scan.tree_source))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::fixtures::section;
+
+    #[test]
+    fn issues_follow_code_quality_format() {
+        let mut moved = section();
+        moved.title_xxx = "./src/main.rs".to_string();
+
+        let report = report([section(), moved].iter(), None);
+
+        assert_eq!(
+            report[0],
+            json!({
+                "type": "issue",
+                "check_name": "rustler/TODO",
+                "description": "TODO: handle errors",
+                "categories": ["Style"],
+                "severity": "minor",
+                "fingerprint": fingerprint::fingerprints([section()].iter(), None)[0],
+                "location": { "path": "src/main.rs", "lines": { "begin": 5 } },
+            })
+        );
+        // fingerprints have to be unique within a report
+        assert_ne!(report[0]["fingerprint"], report[1]["fingerprint"]);
+    }
+}
@@ -0,0 +1,86 @@
+use crate::common_structs::{CommentType, MarkedSection};
+use crate::render::{self, Renderer, Report};
+use std::io::{self, Write};
+
+/// Single line `file:line:col:` formats understood by editors
+#[derive(Clone, Copy, PartialEq)]
+pub enum GrepStyle {
+    /// `file:line:col: TYPE message`, as read by Vim's `:cgetfile` with the default
+    /// `errorformat` and by VS Code problem matchers
+    Vim,
+    /// `file:line:col: severity: TYPE message`, as read by Emacs' `compilation-mode`
+    Emacs,
+}
+
+/// Severity Emacs' `compilation-mode` highlights the line with
+fn emacs_severity(comment_type: &CommentType) -> &'static str {
+    match comment_type {
+        CommentType::XXX => "error",
+        CommentType::Fixme => "warning",
+        _ => "info",
+    }
+}
+
+/// A single special line in the given `style`
+///
+/// Line numbers in notebooks are relative to their cell, so they won't line up with the file.
+pub fn line(section: &MarkedSection, style: GrepStyle) -> String {
+    let tag = section.comment_type.tag().unwrap_or_default();
+    let message = section.message();
+    let text = if message.is_empty() {
+        tag.to_string()
+    } else {
+        format!("{} {}", tag, message)
+    };
+    let location = format!(
+        "{}:{}:{}",
+        section.title_xxx,
+        section.line_number(),
+        section.column()
+    );
+
+    match style {
+        GrepStyle::Vim => format!("{}: {}", location, text),
+        GrepStyle::Emacs => format!(
+            "{}: {}: {}",
+            location,
+            emacs_severity(&section.comment_type),
+            text
+        ),
+    }
+}
+
+/// A [`line`](line) per displayed special line, in the given style
+pub struct Grep(pub GrepStyle);
+
+impl Renderer for Grep {
+    fn render(&self, report: &Report, out: &mut dyn Write) -> io::Result<()> {
+        render::write_lines(out, report.displayed().map(|x| line(x, self.0)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::fixtures::section;
+
+    #[test]
+    fn lines_formatted_per_style() {
+        let mut fixme = section();
+        fixme.comment_type = CommentType::Fixme;
+        fixme.lines[1].content = "// FIXME: leaks".to_string();
+
+        assert_eq!(
+            line(&section(), GrepStyle::Vim),
+            "src/main.rs:5:8: TODO handle errors"
+        );
+        assert_eq!(
+            line(&section(), GrepStyle::Emacs),
+            "src/main.rs:5:8: info: TODO handle errors"
+        );
+        assert_eq!(
+            line(&fixme, GrepStyle::Emacs),
+            "src/main.rs:5:4: warning: FIXME leaks"
+        );
+    }
+}
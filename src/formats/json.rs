@@ -0,0 +1,180 @@
+use crate::common_structs::{BlameInfo, MarkedSection};
+use crate::render::{Renderer, Report};
+use serde_json::{json, Value};
+use std::io::{self, Write};
+
+/// Version of the JSON document, see `schema/report-v1.schema.json`
+///
+/// Bump it (and publish a new schema) on any change that could break existing consumers,
+/// such as removing or renaming a field. Adding a field is a schema change too, since the
+/// published schema doesn't allow unknown properties.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Builds the versioned JSON document for a scan of `root`
+pub fn report<'a>(sections: impl Iterator<Item = &'a MarkedSection>, root: &str) -> Value {
+    json!({
+        "schema_version": SCHEMA_VERSION,
+        "tool": {
+            "name": env!("CARGO_PKG_NAME"),
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "root": root,
+        "breadcrumbs": sections.map(breadcrumb).collect::<Vec<Value>>(),
+    })
+}
+
+/// A single special line, as found in the `breadcrumbs` of the [`report`](report)
+pub fn breadcrumb(section: &MarkedSection) -> Value {
+    json!({
+        "file": section.title_xxx,
+        "line": section.line_number(),
+        "column": section.column(),
+        "type": section.comment_type.tag(),
+        "message": section.message(),
+        "metadata": {
+            "language": section.language.to_string(),
+            "embedded": section.embedded,
+            "cell": section.cell,
+            "blame": section.blame.as_ref().map(blame),
+            "owners": section.owners,
+        },
+        "context": section
+            .lines
+            .iter()
+            .map(|x| json!({ "line": x.number, "text": x.content, "special": x.is_special }))
+            .collect::<Vec<Value>>(),
+    })
+}
+
+fn blame(blame: &BlameInfo) -> Value {
+    json!({
+        "author": blame.author,
+        "email": blame.email,
+        "commit": blame.commit,
+        "date": blame.date(),
+        "timestamp": blame.timestamp,
+    })
+}
+
+/// The [`report`](report) of the displayed special lines, pretty printed
+pub struct Json;
+
+impl Renderer for Json {
+    fn render(&self, scan: &Report, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "{:#}", report(scan.displayed(), scan.root))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common_structs::{CommentType, Line};
+    use crate::formats::fixtures::section;
+
+    const SCHEMA: &str = include_str!("../../schema/report-v1.schema.json");
+
+    #[test]
+    fn report_fields_pinned() {
+        let mut in_cell = section();
+        in_cell.blame = None;
+        in_cell.owners = vec![];
+        in_cell.cell = Some(2);
+        in_cell.lines = vec![Line::new("# TODO: rerun", 1, true)];
+
+        assert_eq!(
+            report([in_cell].iter(), "notebooks"),
+            json!({
+                "schema_version": 1,
+                "tool": {
+                    "name": "rustler",
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+                "root": "notebooks",
+                "breadcrumbs": [{
+                    "file": "src/main.rs",
+                    "line": 1,
+                    "column": 3,
+                    "type": "TODO",
+                    "message": "rerun",
+                    "metadata": {
+                        "language": "rust",
+                        "embedded": false,
+                        "cell": 2,
+                        "blame": null,
+                        "owners": [],
+                    },
+                    "context": [{ "line": 1, "text": "# TODO: rerun", "special": true }],
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn published_schema_requires_output_fields() {
+        let schema: Value = serde_json::from_str(SCHEMA).unwrap();
+        let document = report([section()].iter(), ".");
+
+        assert_eq!(
+            schema["properties"]["schema_version"]["const"],
+            json!(SCHEMA_VERSION)
+        );
+        let required = |x: &Value| x["required"].as_array().unwrap().clone();
+        let breadcrumb_schema = &schema["definitions"]["breadcrumb"];
+        for (schema, value) in [
+            (&schema, &document),
+            (breadcrumb_schema, &document["breadcrumbs"][0]),
+        ] {
+            for key in required(schema) {
+                assert!(
+                    value.get(key.as_str().unwrap()).is_some(),
+                    "{} missing",
+                    key
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn tags_preferred_as_whole_words() {
+        let mut section = section();
+        section.comment_type = CommentType::Note;
+        section.lines[1].content = "# the notebook NOTE: reruns cells".to_string();
+
+        let breadcrumb = breadcrumb(&section);
+
+        assert_eq!(breadcrumb["column"], json!(16));
+        assert_eq!(breadcrumb["message"], json!("reruns cells"));
+    }
+
+    #[test]
+    fn breadcrumb_fields_pinned() {
+        assert_eq!(
+            breadcrumb(&section()),
+            json!({
+                "file": "src/main.rs",
+                "line": 5,
+                "column": 8,
+                "type": "TODO",
+                "message": "handle errors",
+                "metadata": {
+                    "language": "rust",
+                    "embedded": false,
+                    "cell": null,
+                    "blame": {
+                        "author": "Alice",
+                        "email": "alice@example.com",
+                        "commit": "0123456789abcdef",
+                        "date": "2020-09-13",
+                        "timestamp": 1_600_000_000,
+                    },
+                    "owners": ["@org/core"],
+                },
+                "context": [
+                    { "line": 4, "text": "fn main() {", "special": false },
+                    { "line": 5, "text": "    /* TODO(alice): handle errors */", "special": true },
+                    { "line": 6, "text": "}", "special": false },
+                ],
+            })
+        );
+    }
+}
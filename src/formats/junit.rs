@@ -0,0 +1,140 @@
+use super::xml;
+use crate::common_structs::MarkedSection;
+use crate::policy::StalePolicy;
+use crate::render::{Renderer, Report};
+use std::io::{self, Write};
+
+/// Renders the policies as a JUnit XML report, with a test suite per policy and a failing
+/// test case per special line breaking it
+///
+/// A policy nothing breaks gets a single passing test case, so it still shows up as checked.
+pub fn render(policies: &[StalePolicy], sections: &[MarkedSection], now: i64) -> String {
+    let mut suites = vec![];
+    let mut total_tests = 0;
+    let mut total_failures = 0;
+
+    for policy in policies {
+        let description = policy.description();
+        let violations = policy.find_violations(sections, now);
+        let mut suite = String::new();
+
+        if violations.is_empty() {
+            total_tests += 1;
+            suite.push_str(&format!(
+                "  <testsuite name=\"rustler.{}\" tests=\"1\" failures=\"0\">\n",
+                xml::escape(&description)
+            ));
+            suite.push_str(&format!(
+                "    <testcase classname=\"rustler\" name=\"no {}\"/>\n",
+                xml::escape(&description)
+            ));
+        } else {
+            total_tests += violations.len();
+            total_failures += violations.len();
+            suite.push_str(&format!(
+                "  <testsuite name=\"rustler.{}\" tests=\"{}\" failures=\"{}\">\n",
+                xml::escape(&description),
+                violations.len(),
+                violations.len()
+            ));
+            for section in violations {
+                suite.push_str(&testcase(section, &description, now));
+            }
+        }
+        suite.push_str("  </testsuite>\n");
+        suites.push(suite);
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <testsuites name=\"rustler\" tests=\"{}\" failures=\"{}\">\n{}</testsuites>\n",
+        total_tests,
+        total_failures,
+        suites.concat()
+    )
+}
+
+fn testcase(section: &MarkedSection, description: &str, now: i64) -> String {
+    let age = match section.age_days(now) {
+        Some(age) => format!(", {} days old", age),
+        None => String::new(),
+    };
+    let context = section
+        .lines
+        .iter()
+        .map(|x| format!("{}\t{}", x.number, x.content))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    format!(
+        "    <testcase classname=\"{}\" name=\"{}\">\n      \
+         <failure type=\"{}\" message=\"{}\">{}</failure>\n    \
+         </testcase>\n",
+        xml::escape(&section.title_xxx),
+        xml::escape(&section.location()),
+        xml::escape(description),
+        xml::escape(&format!("{}{}", section.message(), age)),
+        xml::escape(&context)
+    )
+}
+
+/// The JUnit report of the policies, checked against all the special lines whatever their type
+pub struct Junit {
+    pub policies: Vec<StalePolicy>,
+}
+
+impl Renderer for Junit {
+    fn render(&self, report: &Report, out: &mut dyn Write) -> io::Result<()> {
+        write!(
+            out,
+            "{}",
+            render(&self.policies, report.sections, report.now)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common_structs::CommentType;
+    use crate::dates::SECONDS_PER_DAY;
+    use crate::formats::fixtures::section;
+
+    #[test]
+    fn failing_testcase_per_violation() {
+        let policy = StalePolicy {
+            comment_type: CommentType::Todo,
+            max_age_days: 90,
+        };
+        let now = section().blame.unwrap().timestamp + 100 * SECONDS_PER_DAY;
+
+        let output = render(&[policy], &[section()], now);
+
+        assert_eq!(
+            output,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <testsuites name=\"rustler\" tests=\"1\" failures=\"1\">\n  \
+               <testsuite name=\"rustler.TODO older than 90 days\" tests=\"1\" failures=\"1\">\n    \
+                 <testcase classname=\"src/main.rs\" name=\"src/main.rs:5\">\n      \
+                   <failure type=\"TODO older than 90 days\" message=\"handle errors, 100 days old\">\
+                   4\tfn main() {\n5\t    /* TODO(alice): handle errors */\n6\t}</failure>\n    \
+                 </testcase>\n  \
+               </testsuite>\n\
+             </testsuites>\n"
+        );
+    }
+
+    #[test]
+    fn passing_testcase_without_violations() {
+        let policy = StalePolicy {
+            comment_type: CommentType::Fixme,
+            max_age_days: 90,
+        };
+
+        let output = render(&[policy], &[section()], 0);
+
+        assert!(output.contains("tests=\"1\" failures=\"0\""));
+        assert!(output
+            .contains("<testcase classname=\"rustler\" name=\"no FIXME older than 90 days\"/>"));
+    }
+}
@@ -0,0 +1,160 @@
+//! Machine readable output formats for the special lines of a scan, picked with `--format`
+
+pub mod checkstyle;
+pub mod csv;
+pub mod fingerprint;
+pub mod github;
+pub mod gitlab;
+pub mod grep;
+pub mod json;
+pub mod junit;
+pub mod ndjson;
+pub mod sarif;
+pub mod taskwarrior;
+pub mod todo_txt;
+pub mod xml;
+
+use crate::git::{self, TreeSource};
+use crate::render::Report;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path};
+
+/// A path relative to the current directory, without a leading `./` and with `/` separators,
+/// as CI services expect them
+pub fn relative_path(path: &str) -> &str {
+    path.trim_start_matches("./")
+}
+
+/// The project a special line belongs to, for task trackers: the name of the closest crate its
+/// file is in, else the top directory of the file under `root`
+///
+/// The `Cargo.toml`s are read from `source`, the tree the file was read from (`None` for the
+/// working directory), so the project always matches the scanned version of the file.
+pub fn project(path: &str, root: &str, source: Option<&TreeSource>) -> Option<String> {
+    let path = Path::new(path);
+    crate_name(Path::new("."), path, source).or_else(|| {
+        // files read from a git tree are already relative to the repository root
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let mut components = relative.components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(dir)), Some(_)) => Some(dir.to_string_lossy().into_owned()),
+            _ => None,
+        }
+    })
+}
+
+/// The [`project`](project) of each file of the displayed special lines of `report`
+pub fn projects(report: &Report) -> HashMap<String, Option<String>> {
+    let mut projects = HashMap::new();
+    for section in report.displayed() {
+        if !projects.contains_key(&section.title_xxx) {
+            let project = project(&section.title_xxx, report.root, report.tree_source);
+            projects.insert(section.title_xxx.clone(), project);
+        }
+    }
+    projects
+}
+
+/// The name of the closest crate the file at `path` is in, with its `Cargo.toml` read from
+/// `source` in the repository `directory` is in, or from the working directory if `None`
+fn crate_name(directory: &Path, path: &Path, source: Option<&TreeSource>) -> Option<String> {
+    let manifest = match source {
+        Some(source) => {
+            let manifests = path
+                .ancestors()
+                .skip(1)
+                .map(|x| x.join("Cargo.toml").to_string_lossy().replace('\\', "/"))
+                .collect::<Vec<String>>();
+            git::read_tree_files(directory, source, &manifests)
+                .ok()?
+                .into_iter()
+                .next()
+                .map(|x| x.lines.join("\n"))
+        }
+        None => path
+            .ancestors()
+            .skip(1)
+            .find_map(|x| fs::read_to_string(directory.join(x).join("Cargo.toml")).ok()),
+    };
+    manifest.and_then(|x| package_name(&x))
+}
+
+/// The `name` of the `[package]` table of a `Cargo.toml`, `None` for a workspace manifest
+fn package_name(manifest: &str) -> Option<String> {
+    let mut in_package = false;
+    for line in manifest.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_package = line == "[package]";
+        } else if let Some(value) = line.strip_prefix("name").map(str::trim_start) {
+            if in_package && value.starts_with('=') {
+                return Some(value[1..].trim().trim_matches(['"', '\'']).to_string());
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn package_name_read_from_manifest() {
+        let manifest = "[workspace]\nmembers = [\"a\"]\n\n[package]\nversion = \"0.1.0\"\nname = \"rustler\"\n";
+        assert_eq!(package_name(manifest), Some("rustler".to_string()));
+        assert_eq!(package_name("[workspace]\nname = \"x\"\n"), None);
+    }
+
+    #[test]
+    fn crate_name_read_from_scanned_tree() {
+        let repo = git::test_repo::init();
+        let dir = repo.path();
+        fs::create_dir(dir.join("src")).unwrap();
+        git::test_repo::commit_file(
+            dir,
+            "Cargo.toml",
+            "[package]\nname = \"committed\"\n",
+            "Alice",
+            "2020-01-10T00:00:00Z",
+        );
+        fs::write(dir.join("Cargo.toml"), "[package]\nname = \"renamed\"\n").unwrap();
+        let path = Path::new("src/lib.rs");
+
+        let head = TreeSource::Revision("HEAD".to_string());
+        assert_eq!(
+            crate_name(dir, path, Some(&head)),
+            Some("committed".to_string())
+        );
+        assert_eq!(crate_name(dir, path, None), Some("renamed".to_string()));
+    }
+}
+
+#[cfg(test)]
+pub mod fixtures {
+    use crate::common_structs::{BlameInfo, CommentType, Line, MarkedSection};
+    use crate::language::Language;
+
+    /// A blamed and owned `TODO` with a line of context on each side
+    pub fn section() -> MarkedSection {
+        MarkedSection {
+            lines: vec![
+                Line::new("fn main() {", 4, false),
+                Line::new("    /* TODO(alice): handle errors */", 5, true),
+                Line::new("}", 6, false),
+            ],
+            comment_type: CommentType::Todo,
+            title_xxx: "src/main.rs".to_string(),
+            language: Language::Rust,
+            embedded: false,
+            cell: None,
+            blame: Some(BlameInfo {
+                author: "Alice".to_string(),
+                email: "alice@example.com".to_string(),
+                commit: "0123456789abcdef".to_string(),
+                timestamp: 1_600_000_000,
+            }),
+            owners: vec!["@org/core".to_string()],
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use super::json;
+use crate::common_structs::MarkedSection;
+use crate::render::{self, Renderer, Report};
+use std::io::{self, Write};
+
+/// A single special line as one line of JSON, the same object as in the `breadcrumbs` of a
+/// [`json::report`](json::report)
+///
+/// There's no document around the lines, so the schema version and tool version are left
+/// out, see `definitions/breadcrumb` in `schema/report-v1.schema.json` for the fields.
+pub fn line(section: &MarkedSection) -> String {
+    json::breadcrumb(section).to_string()
+}
+
+/// A [`line`](line) per displayed special line
+pub struct Ndjson;
+
+impl Renderer for Ndjson {
+    fn render(&self, report: &Report, out: &mut dyn Write) -> io::Result<()> {
+        render::write_lines(out, report.displayed().map(line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::fixtures::section;
+    use serde_json::Value;
+
+    #[test]
+    fn breadcrumbs_fit_on_one_line() {
+        let mut section = section();
+        section.lines[1].content = "// TODO: \"quoted\"\tand tabbed".to_string();
+
+        let line = line(&section);
+
+        assert!(!line.contains('\n'));
+        let parsed: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed, json::breadcrumb(&section));
+    }
+}
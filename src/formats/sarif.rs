@@ -0,0 +1,193 @@
+use super::fingerprint;
+use crate::common_structs::{CommentType, MarkedSection};
+use crate::git::TreeSource;
+use crate::render::{Renderer, Report};
+use serde_json::{json, Value};
+use std::io::{self, Write};
+
+pub const SARIF_VERSION: &str = "2.1.0";
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Key of the fingerprint in each result's `partialFingerprints`, see [`fingerprints`](fingerprint::fingerprints)
+pub const FINGERPRINT_KEY: &str = "rustlerBreadcrumb/v1";
+
+/// One rule per type, in the order of their `ruleIndex`
+const RULES: [CommentType; 4] = [
+    CommentType::Todo,
+    CommentType::Fixme,
+    CommentType::Note,
+    CommentType::XXX,
+];
+
+/// SARIF level of a type: `XXX` marks something dangerous, `FIXME` something broken,
+/// `TODO` missing work and `NOTE` is only informational
+fn level(comment_type: &CommentType) -> &'static str {
+    match comment_type {
+        CommentType::XXX => "error",
+        CommentType::Fixme => "warning",
+        CommentType::Todo => "note",
+        CommentType::Note | CommentType::Other => "none",
+    }
+}
+
+fn description(comment_type: &CommentType) -> &'static str {
+    match comment_type {
+        CommentType::Todo => "Work left to do",
+        CommentType::Fixme => "Known broken code that needs fixing",
+        CommentType::Note => "Note for readers of the code",
+        CommentType::XXX => "Dangerous or hacky code that needs attention",
+        CommentType::Other => "",
+    }
+}
+
+/// Builds a SARIF 2.1.0 log with a single run holding a result per special line
+///
+/// `source` is the tree the files were read from, `None` for the working directory.
+pub fn report<'a>(
+    sections: impl Iterator<Item = &'a MarkedSection> + Clone,
+    source: Option<&TreeSource>,
+) -> Value {
+    let rules = RULES
+        .iter()
+        .map(|x| {
+            let tag = x.tag().unwrap_or_default();
+            json!({
+                "id": tag,
+                "name": x.to_string(),
+                "shortDescription": { "text": format!("{} breadcrumb", tag) },
+                "fullDescription": { "text": description(x) },
+                "defaultConfiguration": { "level": level(x) },
+            })
+        })
+        .collect::<Vec<Value>>();
+
+    let results = sections
+        .clone()
+        .zip(fingerprint::fingerprints(sections, source))
+        .map(|(section, fingerprint)| result(section, &fingerprint, source))
+        .collect::<Vec<Value>>();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }],
+    })
+}
+
+fn result(section: &MarkedSection, fingerprint: &str, source: Option<&TreeSource>) -> Value {
+    let tag = section.comment_type.tag().unwrap_or_default();
+    let message = section.message();
+    let text = if message.is_empty() {
+        tag.to_string()
+    } else {
+        format!("{}: {}", tag, message)
+    };
+
+    let mut result = json!({
+        "ruleId": tag,
+        "ruleIndex": RULES.iter().position(|x| *x == section.comment_type),
+        "level": level(&section.comment_type),
+        "message": { "text": text },
+        "locations": [{
+            "physicalLocation": {
+                "artifactLocation": { "uri": uri(&fingerprint::repo_path(&section.title_xxx, source)) },
+                "region": {
+                    "startLine": section.line_number(),
+                    "startColumn": section.column(),
+                    "snippet": { "text": section.special_line().map_or("", |x| x.content.as_str()) },
+                },
+            },
+        }],
+        "partialFingerprints": { FINGERPRINT_KEY: fingerprint },
+    });
+    // line numbers of notebook cells don't point into the file, so the cell is kept alongside
+    if let Some(cell) = section.cell {
+        result["properties"] = json!({ "notebookCell": cell });
+    }
+    result
+}
+
+/// Relative URI reference for a path relative to the repository root, which code scanning
+/// resolves the `uri`s against
+///
+/// Every byte other than `/` and the unreserved characters of RFC 3986 is percent-encoded.
+fn uri(path: &str) -> String {
+    path.bytes()
+        .map(|x| match x {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                (x as char).to_string()
+            }
+            _ => format!("%{:02X}", x),
+        })
+        .collect()
+}
+
+/// The SARIF log of the displayed special lines, pretty printed
+pub struct Sarif;
+
+impl Renderer for Sarif {
+    fn render(&self, scan: &Report, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "{:#}", report(scan.displayed(), scan.tree_source))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::fixtures::section;
+
+    #[test]
+    fn results_carry_location_level_and_fingerprint() {
+        let mut fixme = section();
+        fixme.comment_type = CommentType::Fixme;
+        fixme.title_xxx = "./src/my lib#2?%.rs".to_string();
+        fixme.lines[1].content = "// FIXME: leaks".to_string();
+        let sections = [section(), fixme];
+
+        let log = report(sections.iter(), None);
+
+        assert_eq!(log["version"], json!("2.1.0"));
+        let run = &log["runs"][0];
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), 4);
+        assert_eq!(run["tool"]["driver"]["rules"][1]["id"], json!("FIXME"));
+
+        let result = &run["results"][1];
+        assert_eq!(result["ruleId"], json!("FIXME"));
+        assert_eq!(result["ruleIndex"], json!(1));
+        assert_eq!(result["level"], json!("warning"));
+        assert_eq!(result["message"]["text"], json!("FIXME: leaks"));
+        let location = &result["locations"][0]["physicalLocation"];
+        assert_eq!(
+            location["artifactLocation"]["uri"],
+            json!("src/my%20lib%232%3F%25.rs")
+        );
+        assert_eq!(location["region"]["startLine"], json!(5));
+        assert_eq!(location["region"]["startColumn"], json!(4));
+        assert_eq!(
+            result["partialFingerprints"][FINGERPRINT_KEY],
+            json!(fingerprint::fingerprints(sections.iter(), None)[1])
+        );
+        assert!(result.get("properties").is_none());
+    }
+
+    #[test]
+    fn fingerprints_stable_across_runs() {
+        let first = report([section()].iter(), None);
+        let second = report([section()].iter(), None);
+
+        assert_eq!(
+            first["runs"][0]["results"][0]["partialFingerprints"],
+            second["runs"][0]["results"][0]["partialFingerprints"]
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use super::fingerprint::{self, fnv1a_64};
+use super::{projects, relative_path};
+use crate::common_structs::{CommentType, MarkedSection};
+use crate::dates::{self, civil_from_timestamp, SECONDS_PER_DAY};
+use crate::git::TreeSource;
+use crate::render::{Renderer, Report};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+fn priority(comment_type: &CommentType) -> Option<&'static str> {
+    match comment_type {
+        CommentType::XXX => Some("H"),
+        CommentType::Fixme => Some("M"),
+        CommentType::Todo => Some("L"),
+        _ => None,
+    }
+}
+
+/// A unix timestamp in the `YYYYMMDDTHHMMSSZ` form of Taskwarrior's dates
+fn task_date(timestamp: i64) -> String {
+    let (year, month, day) = civil_from_timestamp(timestamp);
+    let seconds = timestamp.rem_euclid(SECONDS_PER_DAY);
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// A UUID made from a [`fingerprint`](fingerprint::fingerprints), so importing the same special
+/// line again updates its task instead of adding another one
+fn uuid(fingerprint: &str) -> String {
+    let hex = format!(
+        "{}{:016x}",
+        fingerprint,
+        fnv1a_64(format!("uuid:{}", fingerprint).as_bytes())
+    );
+    // version 8 (custom) and the RFC 4122 variant
+    format!(
+        "{}-{}-8{}-{:x}{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[13..16],
+        u8::from_str_radix(&hex[16..17], 16).unwrap_or_default() & 0x3 | 0x8,
+        &hex[17..20],
+        &hex[20..32]
+    )
+}
+
+/// A task for Taskwarrior's `task import`
+///
+/// Its location is kept in the `file` and `line` user defined attributes, which need to be
+/// declared in the `.taskrc` for Taskwarrior to show them.
+fn task(section: &MarkedSection, fingerprint: &str, project: Option<&str>) -> Value {
+    let tag = section.comment_type.tag().unwrap_or_default();
+    let message = section.message();
+    let mut task = Map::new();
+    task.insert("uuid".to_string(), json!(uuid(fingerprint)));
+    task.insert("status".to_string(), json!("pending"));
+    task.insert(
+        "description".to_string(),
+        json!(if message.is_empty() {
+            tag.to_string()
+        } else {
+            format!("{}: {}", tag, message)
+        }),
+    );
+    task.insert("tags".to_string(), json!([tag.to_lowercase()]));
+    if let Some(priority) = priority(&section.comment_type) {
+        task.insert("priority".to_string(), json!(priority));
+    }
+    if let Some(project) = project {
+        task.insert("project".to_string(), json!(project));
+    }
+    if let Some(blame) = &section.blame {
+        task.insert("entry".to_string(), json!(task_date(blame.timestamp)));
+    }
+    let special_line = section.special_line().map_or("", |x| x.content.as_str());
+    if let Some((year, month, day)) = dates::find_date(special_line) {
+        task.insert(
+            "due".to_string(),
+            json!(format!("{:04}{:02}{:02}T000000Z", year, month, day)),
+        );
+    }
+    task.insert("file".to_string(), json!(relative_path(&section.title_xxx)));
+    task.insert("line".to_string(), json!(section.line_number()));
+    Value::Object(task)
+}
+
+/// The special lines as an array of tasks, ready for `task import`
+///
+/// `projects` holds the [`project`](super::project) of each file of the special lines, and
+/// `source` is the tree the files were read from, `None` for the working directory.
+pub fn report<'a>(
+    sections: impl Iterator<Item = &'a MarkedSection> + Clone,
+    projects: &HashMap<String, Option<String>>,
+    source: Option<&TreeSource>,
+) -> Value {
+    let fingerprints = fingerprint::fingerprints(sections.clone(), source);
+    Value::Array(
+        sections
+            .zip(fingerprints.iter())
+            .map(|(section, fingerprint)| {
+                let project = projects.get(&section.title_xxx).and_then(|x| x.as_deref());
+                task(section, fingerprint, project)
+            })
+            .collect(),
+    )
+}
+
+/// The [`report`](report) of the displayed special lines
+pub struct Taskwarrior;
+
+impl Renderer for Taskwarrior {
+    fn render(&self, scan: &Report, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(
+            out,
+            "{:#}",
+            report(scan.displayed(), &projects(scan), scan.tree_source)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::fixtures::section;
+
+    #[test]
+    fn tasks_importable_with_file_and_line() {
+        let mut section = section();
+        section.comment_type = CommentType::Fixme;
+        section.lines[1].content = "    // FIXME: leaks, by 2025-03-01".to_string();
+
+        let sections = [section];
+        let tasks = report(sections.iter(), &HashMap::new(), None);
+        let task = &tasks[0];
+
+        assert_eq!(task["description"], json!("FIXME: leaks, by 2025-03-01"));
+        assert_eq!(task["priority"], json!("M"));
+        assert_eq!(task["tags"], json!(["fixme"]));
+        assert_eq!(task["entry"], json!("20200913T122640Z"));
+        assert_eq!(task["due"], json!("20250301T000000Z"));
+        assert_eq!(task["file"], json!("src/main.rs"));
+        assert_eq!(task["line"], json!(5));
+        let uuid = task["uuid"].as_str().unwrap();
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(
+            uuid,
+            report(sections.iter(), &HashMap::new(), None)[0]["uuid"]
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use super::{projects, relative_path};
+use crate::common_structs::{CommentType, MarkedSection};
+use crate::dates;
+use crate::render::{self, Renderer, Report};
+use std::io::{self, Write};
+
+/// Priority of a special line, `XXX` first and `NOTE` without one
+fn priority(comment_type: &CommentType) -> Option<char> {
+    match comment_type {
+        CommentType::XXX => Some('A'),
+        CommentType::Fixme => Some('B'),
+        CommentType::Todo => Some('C'),
+        _ => None,
+    }
+}
+
+/// A word without whitespace, as todo.txt projects and contexts must be
+fn word(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join("-")
+}
+
+/// A special line as a todo.txt task
+///
+/// The task is made of the priority, the date it was added (if blamed), the tag and message,
+/// the [`project`](super::project) of the file as `+project`, each owner as an `@context`, the first date written
+/// in the line as `due:` and the location as `file:` and `line:`.
+pub fn line(section: &MarkedSection, project: Option<&str>) -> String {
+    let mut parts = vec![];
+    if let Some(priority) = priority(&section.comment_type) {
+        parts.push(format!("({})", priority));
+    }
+    if let Some(blame) = &section.blame {
+        parts.push(blame.date());
+    }
+    let tag = section.comment_type.tag().unwrap_or_default();
+    match section.message() {
+        message if message.is_empty() => parts.push(tag.to_string()),
+        message => parts.push(format!("{}: {}", tag, message)),
+    }
+    if let Some(project) = project {
+        parts.push(format!("+{}", word(project)));
+    }
+    for owner in &section.owners {
+        parts.push(format!("@{}", word(owner.trim_start_matches('@'))));
+    }
+    let special_line = section.special_line().map_or("", |x| x.content.as_str());
+    if let Some((year, month, day)) = dates::find_date(special_line) {
+        parts.push(format!("due:{:04}-{:02}-{:02}", year, month, day));
+    }
+    parts.push(format!("file:{}", word(relative_path(&section.title_xxx))));
+    parts.push(format!("line:{}", section.line_number()));
+    parts.join(" ")
+}
+
+/// A todo.txt [`line`](line) per displayed special line
+pub struct TodoTxt;
+
+impl Renderer for TodoTxt {
+    fn render(&self, report: &Report, out: &mut dyn Write) -> io::Result<()> {
+        let projects = projects(report);
+        let project_of = |x: &MarkedSection| projects[&x.title_xxx].as_deref();
+        render::write_lines(out, report.displayed().map(|x| line(x, project_of(x))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dates::format_date;
+    use crate::formats::fixtures::section;
+
+    #[test]
+    fn tasks_carry_priority_project_context_and_due_date() {
+        let mut section = section();
+        section.lines[1].content = "    // TODO(2025-03-01): handle errors".to_string();
+
+        assert_eq!(
+            line(&section, Some("my tools")),
+            format!(
+                "(C) {} TODO: handle errors +my-tools @org/core due:2025-03-01 file:src/main.rs line:5",
+                format_date(1_600_000_000)
+            )
+        );
+
+        section.comment_type = CommentType::Note;
+        section.blame = None;
+        section.owners = vec![];
+        section.lines[1].content = "    // NOTE".to_string();
+        assert_eq!(line(&section, None), "NOTE file:src/main.rs line:5");
+    }
+}
@@ -0,0 +1,32 @@
+/// Escapes text for XML attributes and content
+///
+/// Characters XML 1.0 doesn't allow at all (most control characters) are dropped.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for x in text.chars() {
+        match x {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(x),
+            x if x.is_control() => {}
+            x => escaped.push(x),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markup_and_control_characters_escaped() {
+        assert_eq!(
+            escape("if a < b && c > \"d\" {'e'}\u{1b}[0m"),
+            "if a &lt; b &amp;&amp; c &gt; &quot;d&quot; {&apos;e&apos;}[0m"
+        );
+    }
+}
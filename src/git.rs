@@ -0,0 +1,528 @@
+use super::common_structs::{BlameInfo, MarkedSection, ParsedFile};
+use super::notebook;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// A tree of files in the local git repository that can be read without checking it out
+#[derive(Clone, Debug, PartialEq)]
+pub enum TreeSource {
+    /// Any commit-ish git understands, e.g. `HEAD~10`, a tag or a branch name
+    Revision(String),
+    /// The staging area, i.e. what would be committed next
+    Index,
+}
+
+impl TreeSource {
+    /// Name for the blob at `path` in this tree, as understood by `git cat-file`
+    fn blob_spec(&self, path: &str) -> String {
+        match self {
+            Self::Revision(revision) => format!("{}:{}", revision, path),
+            Self::Index => format!(":{}", path),
+        }
+    }
+}
+
+/// Runs `git` with the given args inside of `directory`, returning its stdout
+///
+/// # Errors
+///
+/// Returns an [`io::Error`](io::Error) if git can't be run, or exits with a non-zero status
+/// (e.g. `directory` isn't inside of a git repository).
+pub fn run_git(directory: &Path, args: &[&str]) -> io::Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(directory)
+        .output()?;
+
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(io::Error::other(message));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Absolute path of the top-level directory of the repository `directory` is in
+pub fn repo_root(directory: &Path) -> io::Result<PathBuf> {
+    let output = run_git(directory, &["rev-parse", "--show-toplevel"])?;
+    Ok(PathBuf::from(output.trim_end()))
+}
+
+/// The best common ancestor of two revisions, which `<base>...<head>` compares `<head>` against
+pub fn merge_base(directory: &Path, base: &str, head: &str) -> io::Result<String> {
+    let output = run_git(directory, &["merge-base", base, head])?;
+    Ok(output.trim_end().to_string())
+}
+
+/// Path of a section's file relative to the repository `root`, as used by git
+///
+/// Sections read from a git tree already have one, the ones read from the working
+/// directory are relative to the current directory instead.
+pub fn repo_relative_path(path: &str, root: &Path, source: Option<&TreeSource>) -> Option<String> {
+    if source.is_some() {
+        return Some(path.to_string());
+    }
+    let absolute = fs::canonicalize(path).ok()?;
+    let root = fs::canonicalize(root).ok()?;
+    let relative = absolute.strip_prefix(root).ok()?;
+    Some(
+        relative
+            .components()
+            .map(|x| x.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+    )
+}
+
+/// Paths (relative to the repository root) of the files in `source` that match `pathspec`
+///
+/// The pathspec is relative to `directory`, like it would be for `git ls-files`.
+pub fn list_files(
+    directory: &Path,
+    source: &TreeSource,
+    pathspec: &str,
+) -> io::Result<Vec<String>> {
+    let output = match source {
+        TreeSource::Revision(revision) => run_git(
+            directory,
+            &[
+                "ls-tree",
+                "-r",
+                "-z",
+                "--name-only",
+                "--full-name",
+                revision,
+                "--",
+                pathspec,
+            ],
+        )?,
+        TreeSource::Index => run_git(
+            directory,
+            &["ls-files", "-z", "--cached", "--full-name", "--", pathspec],
+        )?,
+    };
+    Ok(split_nul_separated(&output))
+}
+
+/// Paths (relative to the repository root) of the files that differ between the two trees
+///
+/// Renames are listed as a removed and an added file, so both paths show up.
+pub fn changed_files(root: &Path, base: &TreeSource, head: &TreeSource) -> io::Result<Vec<String>> {
+    let mut args = vec!["diff", "--name-only", "-z", "--no-renames"];
+    match (base, head) {
+        (TreeSource::Revision(base), TreeSource::Revision(head)) => {
+            args.extend(&[base.as_str(), head.as_str()]);
+        }
+        (TreeSource::Revision(base), TreeSource::Index) => {
+            args.extend(&["--cached", base.as_str()]);
+        }
+        _ => {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "the index can only be compared against a revision",
+            ))
+        }
+    }
+    let output = run_git(root, &args)?;
+    Ok(split_nul_separated(&output))
+}
+
+/// Reads the given files out of `source` as [`ParsedFile`s](ParsedFile), using one
+/// `git cat-file --batch` process for all of them
+///
+/// Files that don't exist in the tree or aren't valid UTF-8 are skipped, notebooks
+/// get split up into their cells.
+pub fn read_tree_files(
+    root: &Path,
+    source: &TreeSource,
+    paths: &[String],
+) -> io::Result<Vec<ParsedFile>> {
+    let mut files = vec![];
+    for (path, contents) in paths.iter().zip(read_tree_texts(root, source, paths)?) {
+        let contents = match contents {
+            Some(contents) => contents,
+            None => continue,
+        };
+        if notebook::is_notebook(Path::new(path)) {
+            if let Ok(parsed_notebook) = notebook::parse_notebook(path, &contents) {
+                files.extend(parsed_notebook.cells);
+            }
+            continue;
+        }
+        let lines = contents.lines().map(|x| x.to_string()).collect();
+        files.push(ParsedFile::new(path.clone(), lines));
+    }
+    Ok(files)
+}
+
+/// Reads the raw contents of the given files out of `source`, `None` for the ones that
+/// don't exist in the tree or aren't valid UTF-8
+pub fn read_tree_texts(
+    root: &Path,
+    source: &TreeSource,
+    paths: &[String],
+) -> io::Result<Vec<Option<String>>> {
+    let specs = paths
+        .iter()
+        .map(|x| source.blob_spec(x))
+        .collect::<Vec<String>>();
+    Ok(cat_file_batch(root, &specs)?
+        .into_iter()
+        .map(|x| x.and_then(|x| String::from_utf8(x).ok()))
+        .collect())
+}
+
+/// Reads the contents of each object named in `specs`, `None` for the ones that are missing
+fn cat_file_batch(root: &Path, specs: &[String]) -> io::Result<Vec<Option<Vec<u8>>>> {
+    let mut child = Command::new("git")
+        .args(["cat-file", "--batch"])
+        .current_dir(root)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    // written from another thread so a full stdout pipe can't block the input
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = specs.iter().map(|x| format!("{}\n", x)).collect::<String>();
+    let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+
+    let mut output = vec![];
+    child
+        .stdout
+        .take()
+        .expect("stdout is piped")
+        .read_to_end(&mut output)?;
+    child.wait()?;
+    writer
+        .join()
+        .map_err(|_| io::Error::other("failed writing to git cat-file"))??;
+
+    let mut blobs = Vec::with_capacity(specs.len());
+    let mut rest = output.as_slice();
+    for _ in specs {
+        let header_end = match rest.iter().position(|x| *x == b'\n') {
+            Some(idx) => idx,
+            None => break,
+        };
+        let header = String::from_utf8_lossy(&rest[..header_end]).into_owned();
+        rest = &rest[header_end + 1..];
+
+        // `<sha> <type> <size>` for found objects, `<spec> missing` otherwise
+        let size = header
+            .rsplit(' ')
+            .next()
+            .and_then(|x| x.parse::<usize>().ok());
+        match size {
+            Some(size) if !header.ends_with(" missing") && size <= rest.len() => {
+                let is_blob = header.split(' ').nth(1) == Some("blob");
+                blobs.push(if is_blob {
+                    Some(rest[..size].to_vec())
+                } else {
+                    None
+                });
+                // contents are followed by a newline
+                rest = &rest[(size + 1).min(rest.len())..];
+            }
+            _ => blobs.push(None),
+        }
+    }
+    blobs.resize(specs.len(), None);
+    Ok(blobs)
+}
+
+fn split_nul_separated(output: &str) -> Vec<String> {
+    output
+        .split('\0')
+        .filter(|x| !x.is_empty())
+        .map(|x| x.to_string())
+        .collect()
+}
+
+/// Blames every line of the file at `file_path`
+///
+/// # Returns
+///
+/// A map of 1-based line numbers to their [`BlameInfo`](BlameInfo). Lines that haven't
+/// been committed yet are left out.
+pub fn blame_file(file_path: &Path) -> io::Result<HashMap<usize, BlameInfo>> {
+    let directory = match file_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let filename = file_path
+        .file_name()
+        .and_then(|x| x.to_str())
+        .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "invalid file name"))?;
+
+    let output = run_git(directory, &["blame", "--line-porcelain", "--", filename])?;
+    Ok(parse_line_porcelain(&output))
+}
+
+/// Same as [`blame_file`](blame_file), for the file at `path` (relative to the repository root) as of `revision`
+pub fn blame_file_at_revision(
+    root: &Path,
+    revision: &str,
+    path: &str,
+) -> io::Result<HashMap<usize, BlameInfo>> {
+    let output = run_git(root, &["blame", "--line-porcelain", revision, "--", path])?;
+    Ok(parse_line_porcelain(&output))
+}
+
+/// Attaches [`BlameInfo`](BlameInfo) to each of the sections, running `git blame` once per file
+///
+/// `source` is the tree the sections were read from, `None` for the working directory.
+///
+/// Sections in files outside of a git repository (and in notebook cells, where line
+/// numbers don't match the file) are left without blame info, as are sections read from
+/// the index, since staged lines have no commit to blame yet.
+pub fn attach_blame(sections: &mut [MarkedSection], source: Option<&TreeSource>) {
+    let mut blames: HashMap<String, Option<HashMap<usize, BlameInfo>>> = HashMap::new();
+    let root = source.and_then(|_| repo_root(Path::new(".")).ok());
+
+    for section in sections.iter_mut().filter(|x| x.cell.is_none()) {
+        let file_blame = blames
+            .entry(section.title_xxx.clone())
+            .or_insert_with_key(|filename| match (source, &root) {
+                (None, _) => blame_file(Path::new(filename)).ok(),
+                (Some(TreeSource::Revision(revision)), Some(root)) => {
+                    blame_file_at_revision(root, revision, filename).ok()
+                }
+                _ => None,
+            });
+
+        section.blame = file_blame
+            .as_ref()
+            .and_then(|x| x.get(&section.line_number()))
+            .cloned();
+    }
+}
+
+/// Parses the output of `git blame --line-porcelain`, where each line of the file gets
+/// a header (`<sha> <original line> <final line> [<group size>]`), a set of `key value`
+/// lines and then the line itself prefixed with a tab
+fn parse_line_porcelain(output: &str) -> HashMap<usize, BlameInfo> {
+    let mut blames = HashMap::new();
+    let mut current: Option<(usize, BlameInfo)> = None;
+
+    for line in output.lines() {
+        if line.starts_with('\t') {
+            if let Some((line_number, blame)) = current.take() {
+                if !is_uncommitted(&blame.commit) {
+                    blames.insert(line_number, blame);
+                }
+            }
+            continue;
+        }
+
+        match &mut current {
+            None => {
+                let mut parts = line.split(' ');
+                let commit = parts.next().unwrap_or_default().to_string();
+                if let Some(line_number) = parts.nth(1).and_then(|x| x.parse().ok()) {
+                    current = Some((
+                        line_number,
+                        BlameInfo {
+                            author: String::new(),
+                            email: String::new(),
+                            commit,
+                            timestamp: 0,
+                        },
+                    ));
+                }
+            }
+            Some((_, blame)) => {
+                let (key, value) = line.split_at(line.find(' ').unwrap_or(line.len()));
+                let value = value.trim_start();
+                match key {
+                    "author" => blame.author = value.to_string(),
+                    "author-mail" => {
+                        blame.email = value.trim_matches(|x| x == '<' || x == '>').to_string()
+                    }
+                    "author-time" => blame.timestamp = value.parse().unwrap_or(0),
+                    _ => {}
+                }
+            }
+        }
+    }
+    blames
+}
+
+fn is_uncommitted(commit: &str) -> bool {
+    commit.chars().all(|x| x == '0')
+}
+
+#[cfg(test)]
+pub mod test_repo {
+    use super::run_git;
+    use std::fs;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    /// Creates an empty git repository in a temporary directory
+    pub fn init() -> TempDir {
+        let dir = tempfile::tempdir().expect("temp dir should be created");
+        run_git(dir.path(), &["init", "-q"]).expect("git init should succeed");
+        dir
+    }
+
+    /// Writes `contents` to `filename` and commits it with a fixed author and date
+    pub fn commit_file(dir: &Path, filename: &str, contents: &str, author: &str, date: &str) {
+        let message = format!("update {}", filename);
+        commit_file_with_message(dir, filename, contents, author, date, &message);
+    }
+
+    pub fn commit_file_with_message(
+        dir: &Path,
+        filename: &str,
+        contents: &str,
+        author: &str,
+        date: &str,
+        message: &str,
+    ) {
+        fs::write(dir.join(filename), contents).expect("file should be written");
+        run_git(dir, &["add", filename]).expect("git add should succeed");
+        let author_arg = format!(
+            "--author={} <{}@example.com>",
+            author,
+            author.to_lowercase()
+        );
+        let date_arg = format!("--date={}", date);
+        run_git(
+            dir,
+            &[
+                "-c",
+                &format!("user.name={}", author),
+                "-c",
+                "user.email=committer@example.com",
+                "commit",
+                "-q",
+                "--no-gpg-sign",
+                &author_arg,
+                &date_arg,
+                "-m",
+                message,
+            ],
+        )
+        .expect("git commit should succeed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn lines_blamed_to_their_commits() {
+        let repo = test_repo::init();
+        test_repo::commit_file(
+            repo.path(),
+            "lib.rs",
+            "fn a() {}\n",
+            "Alice",
+            "2020-01-02T00:00:00Z",
+        );
+        test_repo::commit_file(
+            repo.path(),
+            "lib.rs",
+            "fn a() {}\n// TODO: b\n",
+            "Bob",
+            "2021-03-04T00:00:00Z",
+        );
+
+        let blame = blame_file(&repo.path().join("lib.rs")).expect("blame should succeed");
+
+        assert_eq!(blame[&1].author, "Alice");
+        assert_eq!(blame[&2].author, "Bob");
+        assert_eq!(blame[&2].email, "bob@example.com");
+        assert_eq!(blame[&2].date(), "2021-03-04");
+        assert_eq!(blame[&2].commit.len(), 40);
+    }
+
+    #[test]
+    fn uncommitted_lines_left_out() {
+        let repo = test_repo::init();
+        test_repo::commit_file(
+            repo.path(),
+            "lib.rs",
+            "fn a() {}\n",
+            "Alice",
+            "2020-01-02T00:00:00Z",
+        );
+        fs::write(repo.path().join("lib.rs"), "fn a() {}\n// TODO: wip\n").unwrap();
+
+        let blame = blame_file(&repo.path().join("lib.rs")).expect("blame should succeed");
+
+        assert!(blame.contains_key(&1));
+        assert!(
+            !blame.contains_key(&2),
+            "uncommitted line should have no blame"
+        );
+    }
+
+    #[test]
+    fn merge_base_of_diverged_revisions() {
+        let repo = test_repo::init();
+        let dir = repo.path();
+        test_repo::commit_file(dir, "a.rs", "// base\n", "Alice", "2020-01-10T00:00:00Z");
+        let base = run_git(dir, &["rev-parse", "HEAD"]).unwrap();
+        run_git(dir, &["checkout", "-q", "-b", "topic"]).unwrap();
+        test_repo::commit_file(dir, "a.rs", "// topic\n", "Alice", "2020-01-11T00:00:00Z");
+        run_git(dir, &["checkout", "-q", "-"]).unwrap();
+        test_repo::commit_file(dir, "b.rs", "// main\n", "Alice", "2020-01-12T00:00:00Z");
+
+        assert_eq!(merge_base(dir, "HEAD", "topic").unwrap(), base.trim_end());
+    }
+
+    #[test]
+    fn files_listed_from_revision_and_index() {
+        let repo = test_repo::init();
+        let dir = repo.path();
+        test_repo::commit_file(dir, "a.rs", "// TODO\n", "Alice", "2020-01-02T00:00:00Z");
+        fs::create_dir(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub/b.rs"), "// FIXME\n").unwrap();
+        run_git(dir, &["add", "sub/b.rs"]).unwrap();
+        fs::write(dir.join("untracked.rs"), "// XXX\n").unwrap();
+
+        let head = TreeSource::Revision("HEAD".to_string());
+        assert_eq!(list_files(dir, &head, ".").unwrap(), vec!["a.rs"]);
+        assert_eq!(
+            list_files(dir, &TreeSource::Index, ".").unwrap(),
+            vec!["a.rs", "sub/b.rs"]
+        );
+        assert_eq!(
+            list_files(&dir.join("sub"), &TreeSource::Index, ".").unwrap(),
+            vec!["sub/b.rs"],
+            "pathspec should be relative to the directory, paths to the root"
+        );
+    }
+
+    #[test]
+    fn blame_read_at_revision() {
+        let repo = test_repo::init();
+        let dir = repo.path();
+        test_repo::commit_file(
+            dir,
+            "a.rs",
+            "// TODO: one\n",
+            "Alice",
+            "2020-01-02T00:00:00Z",
+        );
+        test_repo::commit_file(dir, "a.rs", "fn a() {}\n", "Bob", "2021-01-02T00:00:00Z");
+
+        let blame = blame_file_at_revision(dir, "HEAD~1", "a.rs").unwrap();
+        assert_eq!(blame[&1].author, "Alice");
+    }
+
+    #[test]
+    fn file_outside_repository_returns_error() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("lib.rs"), "// TODO\n").unwrap();
+
+        assert!(blame_file(&dir.path().join("lib.rs")).is_err());
+    }
+}
@@ -0,0 +1,455 @@
+use super::common_structs::{
+    BlameInfo, CommentType, MarkedSection, ParseData, ParsedFile, ScanOptions,
+};
+use super::dates;
+use super::git;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::Path;
+
+/// Marks the start of each commit in the `git log` output, followed by `%x1f` separated fields
+const COMMIT_MARKER: &str = "@@@rustler-commit ";
+
+/// When a single breadcrumb was introduced and resolved (removed), and by whom
+pub struct Lifecycle {
+    /// Path of the file the breadcrumb was last seen in, relative to the repository root
+    pub path: String,
+    pub comment_type: CommentType,
+    /// The special line, trimmed
+    pub text: String,
+    /// `None` if the breadcrumb already existed at the start of the walked range
+    pub introduced: Option<BlameInfo>,
+    /// `None` if the breadcrumb is still around at the end of the walked range
+    pub resolved: Option<BlameInfo>,
+}
+
+/// Introduced and resolved breadcrumb counts for a single month
+#[derive(Debug, Default, PartialEq)]
+pub struct MonthStats {
+    pub introduced: usize,
+    pub resolved: usize,
+}
+
+pub struct HistoryReport {
+    pub lifecycles: Vec<Lifecycle>,
+}
+
+impl HistoryReport {
+    /// Introduced and resolved counts keyed by `YYYY-MM`, in chronological order
+    pub fn monthly_stats(&self) -> BTreeMap<String, MonthStats> {
+        let mut stats: BTreeMap<String, MonthStats> = BTreeMap::new();
+        for lifecycle in &self.lifecycles {
+            if let Some(introduced) = &lifecycle.introduced {
+                stats.entry(month_of(introduced)).or_default().introduced += 1;
+            }
+            if let Some(resolved) = &lifecycle.resolved {
+                stats.entry(month_of(resolved)).or_default().resolved += 1;
+            }
+        }
+        stats
+    }
+}
+
+fn month_of(commit: &BlameInfo) -> String {
+    let (year, month, _) = dates::civil_from_timestamp(commit.timestamp);
+    format!("{:04}-{:02}", year, month)
+}
+
+/// Walks the history of the repository `directory` is in, oldest commit first, and tracks
+/// every breadcrumb from the commit that added it to the commit that removed it
+///
+/// # Arguments
+///
+/// * `range` - Revision range to walk, as understood by `git log` (e.g. `v1.0..HEAD`)
+///
+/// # Notes
+///
+/// Breadcrumbs are matched by their type and text. If a commit removes a breadcrumb and
+/// adds the same one back (e.g. it was moved, or its file got renamed) it's kept as the
+/// same breadcrumb, now living at the new path.
+///
+/// Only the first parent of merges is followed, so the branches merged in don't interleave
+/// their commits with the mainline: their breadcrumbs count as introduced and resolved by the
+/// merge commit, at the time they landed.
+pub fn walk_history(
+    directory: &Path,
+    range: &str,
+    options: &ScanOptions,
+) -> io::Result<HistoryReport> {
+    let format = format!("--format={}%H%x1f%an%x1f%ae%x1f%at", COMMIT_MARKER);
+    let output = git::run_git(
+        directory,
+        &[
+            "log",
+            "--reverse",
+            "-p",
+            "-U0",
+            "--first-parent",
+            "-m",
+            "--no-renames",
+            "--no-color",
+            "--no-ext-diff",
+            // the paths are parsed out of `+++ b/<path>`, whatever the diff config says
+            "--src-prefix=a/",
+            "--dst-prefix=b/",
+            &format,
+            range,
+            "--",
+        ],
+    )?;
+    Ok(build_report(parse_log(&output), options))
+}
+
+/// A single line added or removed by a commit
+struct ChangedLine {
+    path: String,
+    content: String,
+    is_added: bool,
+}
+
+struct CommitChanges {
+    commit: BlameInfo,
+    lines: Vec<ChangedLine>,
+}
+
+/// Parses the output of `git log -p -U0` using the [`COMMIT_MARKER`](COMMIT_MARKER) format
+fn parse_log(output: &str) -> Vec<CommitChanges> {
+    let mut commits: Vec<CommitChanges> = vec![];
+    let mut old_path = String::new();
+    let mut path = String::new();
+    // lines left in the current hunk, as (removed, added)
+    let mut hunk_left = (0, 0);
+
+    for line in output.lines() {
+        if hunk_left.0 + hunk_left.1 > 0 {
+            if let Some(commit) = commits.last_mut() {
+                if let Some(content) = line.strip_prefix('-') {
+                    hunk_left.0 -= 1;
+                    commit.lines.push(ChangedLine {
+                        path: path.clone(),
+                        content: content.to_string(),
+                        is_added: false,
+                    });
+                } else if let Some(content) = line.strip_prefix('+') {
+                    hunk_left.1 -= 1;
+                    commit.lines.push(ChangedLine {
+                        path: path.clone(),
+                        content: content.to_string(),
+                        is_added: true,
+                    });
+                }
+            }
+            continue;
+        }
+
+        if let Some(fields) = line.strip_prefix(COMMIT_MARKER) {
+            let fields = fields.split('\x1f').collect::<Vec<&str>>();
+            if fields.len() == 4 {
+                commits.push(CommitChanges {
+                    commit: BlameInfo {
+                        commit: fields[0].to_string(),
+                        author: fields[1].to_string(),
+                        email: fields[2].to_string(),
+                        timestamp: fields[3].parse().unwrap_or(0),
+                    },
+                    lines: vec![],
+                });
+            }
+        } else if let Some(removed_path) = line.strip_prefix("--- ") {
+            old_path = removed_path
+                .strip_prefix("a/")
+                .unwrap_or(removed_path)
+                .to_string();
+        } else if let Some(added_path) = line.strip_prefix("+++ ") {
+            path = match added_path.strip_prefix("b/") {
+                Some(added_path) => added_path.to_string(),
+                // deleted files only have their old path
+                None => old_path.clone(),
+            };
+        } else if line.starts_with("@@ ") {
+            hunk_left = parse_hunk_header(line);
+        }
+    }
+    commits
+}
+
+/// Reads the removed and added line counts out of a `@@ -a,b +c,d @@` hunk header
+pub fn parse_hunk_header(line: &str) -> (usize, usize) {
+    let count = |range: Option<&str>| {
+        range.map_or(0, |x| match x.split_once(',') {
+            Some((_, count)) => count.parse().unwrap_or(0),
+            None => 1,
+        })
+    };
+    let mut parts = line.split(' ').skip(1);
+    let removed = count(parts.next().and_then(|x| x.strip_prefix('-')));
+    let added = count(parts.next().and_then(|x| x.strip_prefix('+')));
+    (removed, added)
+}
+
+/// Checks a single changed line of the file at `path` for a breadcrumb, using the same
+/// detection as a regular scan
+///
+/// Lines are checked on their own, so block comments spanning multiple lines only count on
+/// the lines with a comment marker.
+pub fn breadcrumb_type(path: &str, content: &str, options: &ScanOptions) -> Option<CommentType> {
+    let file = ParsedFile::new(path.to_string(), vec![content.to_string()]);
+    MarkedSection::unpack_lines(ParseData::File(file), options)
+        .pop()
+        .map(|x| x.comment_type)
+}
+
+fn build_report(commits: Vec<CommitChanges>, options: &ScanOptions) -> HistoryReport {
+    let mut lifecycles: Vec<Lifecycle> = vec![];
+    // indices of the lifecycles still open, by type and text
+    let mut open: HashMap<(String, String), Vec<usize>> = HashMap::new();
+
+    for changes in commits {
+        let mut removed = vec![];
+        let mut added = vec![];
+        for changed_line in changes.lines {
+            if let Some(comment_type) =
+                breadcrumb_type(&changed_line.path, &changed_line.content, options)
+            {
+                let key = (
+                    comment_type.to_string(),
+                    changed_line.content.trim().to_string(),
+                );
+                let entry = (key, comment_type, changed_line.path);
+                if changed_line.is_added {
+                    added.push(entry);
+                } else {
+                    removed.push(entry);
+                }
+            }
+        }
+
+        for (key, comment_type, path) in added {
+            // removed and added back in the same commit, so it was only moved
+            if let Some(position) = removed.iter().position(|x| x.0 == key) {
+                removed.remove(position);
+                if let Some(idx) = open.get(&key).and_then(|x| x.last()) {
+                    lifecycles[*idx].path = path;
+                }
+                continue;
+            }
+
+            open.entry(key.clone()).or_default().push(lifecycles.len());
+            lifecycles.push(Lifecycle {
+                path,
+                comment_type,
+                text: key.1,
+                introduced: Some(changes.commit.clone()),
+                resolved: None,
+            });
+        }
+
+        for (key, comment_type, path) in removed {
+            match open.get_mut(&key).and_then(|x| x.pop()) {
+                Some(idx) => lifecycles[idx].resolved = Some(changes.commit.clone()),
+                // introduced before the start of the range
+                None => lifecycles.push(Lifecycle {
+                    path,
+                    comment_type,
+                    text: key.1,
+                    introduced: None,
+                    resolved: Some(changes.commit.clone()),
+                }),
+            }
+        }
+    }
+
+    HistoryReport { lifecycles }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_repo;
+
+    fn summary(report: &HistoryReport) -> Vec<(String, String, Option<String>, Option<String>)> {
+        report
+            .lifecycles
+            .iter()
+            .map(|x| {
+                (
+                    x.path.clone(),
+                    x.text.clone(),
+                    x.introduced.as_ref().map(|x| x.author.clone()),
+                    x.resolved.as_ref().map(|x| x.author.clone()),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hunk_header_counts_parsed() {
+        assert_eq!(parse_hunk_header("@@ -3,2 +4,0 @@ fn a()"), (2, 0));
+        assert_eq!(parse_hunk_header("@@ -3 +4 @@"), (1, 1));
+        assert_eq!(parse_hunk_header("@@ -0,0 +1,3 @@"), (0, 3));
+    }
+
+    #[test]
+    fn lifecycles_tracked_across_commits() {
+        let repo = test_repo::init();
+        let dir = repo.path();
+        test_repo::commit_file(
+            dir,
+            "a.rs",
+            "// TODO: one\n// FIXME: two\n",
+            "Alice",
+            "2020-01-10T00:00:00Z",
+        );
+        test_repo::commit_file(
+            dir,
+            "a.rs",
+            "fn a() {}\n// TODO: one\n",
+            "Bob",
+            "2020-02-10T00:00:00Z",
+        );
+        test_repo::commit_file(dir, "a.rs", "fn a() {}\n", "Carol", "2020-02-20T00:00:00Z");
+
+        let report = walk_history(dir, "HEAD", &ScanOptions::default()).unwrap();
+
+        assert_eq!(
+            summary(&report),
+            vec![
+                (
+                    "a.rs".to_string(),
+                    "// TODO: one".to_string(),
+                    Some("Alice".to_string()),
+                    Some("Carol".to_string())
+                ),
+                (
+                    "a.rs".to_string(),
+                    "// FIXME: two".to_string(),
+                    Some("Alice".to_string()),
+                    Some("Bob".to_string())
+                ),
+            ]
+        );
+
+        let stats = report.monthly_stats();
+        assert_eq!(
+            stats["2020-01"],
+            MonthStats {
+                introduced: 2,
+                resolved: 0
+            }
+        );
+        assert_eq!(
+            stats["2020-02"],
+            MonthStats {
+                introduced: 0,
+                resolved: 2
+            }
+        );
+    }
+
+    #[test]
+    fn merged_branches_count_from_their_merge() {
+        let repo = test_repo::init();
+        let dir = repo.path();
+        git::run_git(dir, &["config", "diff.noprefix", "true"]).unwrap();
+        test_repo::commit_file(dir, "a.rs", "fn a() {}\n", "Alice", "2020-01-10T00:00:00Z");
+        git::run_git(dir, &["checkout", "-q", "-b", "topic"]).unwrap();
+        std::fs::create_dir(dir.join("b")).unwrap();
+        test_repo::commit_file(
+            dir,
+            "b/topic.rs",
+            "// TODO: topic\n",
+            "Bob",
+            "2020-02-10T00:00:00Z",
+        );
+        git::run_git(dir, &["checkout", "-q", "-"]).unwrap();
+        test_repo::commit_file(dir, "c.rs", "fn c() {}\n", "Carol", "2020-03-10T00:00:00Z");
+        git::run_git(
+            dir,
+            &[
+                "-c",
+                "user.name=Dave",
+                "-c",
+                "user.email=dave@example.com",
+                "merge",
+                "-q",
+                "--no-ff",
+                "--no-edit",
+                "--no-gpg-sign",
+                "topic",
+            ],
+        )
+        .unwrap();
+
+        let report = walk_history(dir, "HEAD", &ScanOptions::default()).unwrap();
+
+        assert_eq!(
+            summary(&report),
+            vec![(
+                "b/topic.rs".to_string(),
+                "// TODO: topic".to_string(),
+                Some("Dave".to_string()),
+                None
+            )]
+        );
+    }
+
+    #[test]
+    fn moved_breadcrumbs_keep_their_introduction() {
+        let repo = test_repo::init();
+        let dir = repo.path();
+        test_repo::commit_file(
+            dir,
+            "a.rs",
+            "// TODO: wander\n",
+            "Alice",
+            "2020-01-10T00:00:00Z",
+        );
+        std::fs::remove_file(dir.join("a.rs")).unwrap();
+        git::run_git(dir, &["rm", "-q", "--cached", "a.rs"]).unwrap();
+        test_repo::commit_file(
+            dir,
+            "b.rs",
+            "// TODO: wander\n",
+            "Bob",
+            "2020-02-10T00:00:00Z",
+        );
+
+        let report = walk_history(dir, "HEAD", &ScanOptions::default()).unwrap();
+
+        assert_eq!(
+            summary(&report),
+            vec![(
+                "b.rs".to_string(),
+                "// TODO: wander".to_string(),
+                Some("Alice".to_string()),
+                None
+            )]
+        );
+    }
+
+    #[test]
+    fn breadcrumbs_from_before_range_have_no_introduction() {
+        let repo = test_repo::init();
+        let dir = repo.path();
+        test_repo::commit_file(
+            dir,
+            "a.py",
+            "# TODO: old\n",
+            "Alice",
+            "2020-01-10T00:00:00Z",
+        );
+        test_repo::commit_file(dir, "a.py", "\n", "Bob", "2020-02-10T00:00:00Z");
+
+        let report = walk_history(dir, "HEAD~1..HEAD", &ScanOptions::default()).unwrap();
+
+        assert_eq!(
+            summary(&report),
+            vec![(
+                "a.py".to_string(),
+                "# TODO: old".to_string(),
+                None,
+                Some("Bob".to_string())
+            )]
+        );
+    }
+}
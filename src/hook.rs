@@ -0,0 +1,397 @@
+use super::common_structs::{CommentType, ScanOptions};
+use super::git::{self, TreeSource};
+use super::history;
+use super::language::{self, Language, LineRegion};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+/// Marks hooks written by [`install_pre_commit`](install_pre_commit), so they can be safely replaced
+const HOOK_MARKER: &str = "# installed by rustler";
+
+/// A line added by the staged changes
+pub struct AddedLine {
+    /// Path of the file, relative to the repository root
+    pub path: String,
+    /// 1-based line number in the staged version of the file
+    pub number: usize,
+    pub content: String,
+}
+
+pub enum Problem {
+    /// The line has a tag that isn't allowed in new code
+    Forbidden(CommentType),
+    /// The line has a `TODO` that isn't written as `TODO: message` or `TODO(owner): message`
+    MalformedTodo,
+}
+
+/// A problem found in a single staged line, see [`check_staged`](check_staged)
+pub struct Violation {
+    pub line: AddedLine,
+    pub problem: Problem,
+}
+
+/// Checks the lines added by the staged changes of the repository `directory` is in
+///
+/// # Arguments
+///
+/// * `forbidden` - Types of breadcrumbs that can't be added at all (e.g. `FIXME` and `XXX`)
+///
+/// # Notes
+///
+/// Only newly added lines are checked, breadcrumbs that were already committed and the
+/// unstaged changes in the working directory are left alone.
+pub fn check_staged(directory: &Path, forbidden: &[CommentType]) -> io::Result<Vec<Violation>> {
+    let output = git::run_git(
+        directory,
+        &[
+            "diff",
+            "--cached",
+            "-U0",
+            "--no-renames",
+            "--no-color",
+            "--no-ext-diff",
+            // the paths are parsed out of `+++ b/<path>`, whatever the diff config says
+            "--src-prefix=a/",
+            "--dst-prefix=b/",
+        ],
+    )?;
+    let options = ScanOptions::default();
+    let added_lines = parse_added_lines(&output);
+    let regions = staged_regions(directory, &added_lines)?;
+
+    Ok(added_lines
+        .into_iter()
+        .filter_map(|line| {
+            let host = Language::from_path(&line.path);
+            let region = regions
+                .get(&line.path)
+                .and_then(|x| x.get(line.number.wrapping_sub(1)))
+                .copied()
+                .unwrap_or(LineRegion {
+                    language: host,
+                    is_comment: false,
+                });
+            let comment = comment_text(&region, &line.content);
+            // blocking a commit needs a tag written as a whole word in a comment, prose and task
+            // list items are the only breadcrumbs without one, and code in embedded regions has
+            // neither
+            let comment_type = match comment {
+                Some(comment) => CommentType::find_tag(comment)?.0,
+                None if region.language == host => {
+                    history::breadcrumb_type(&line.path, &line.content, &options)?
+                }
+                None => return None,
+            };
+            let problem = if forbidden.contains(&comment_type) {
+                Problem::Forbidden(comment_type)
+            } else if comment_type == CommentType::Todo && comment.is_some_and(is_malformed_todo) {
+                Problem::MalformedTodo
+            } else {
+                return None;
+            };
+            Some(Violation { line, problem })
+        })
+        .collect())
+}
+
+/// Parses the added lines, with their new line numbers, out of the output of `git diff -U0`
+fn parse_added_lines(output: &str) -> Vec<AddedLine> {
+    let mut added_lines = vec![];
+    let mut path = String::new();
+    // next line number in the new file, and the lines left in the current hunk as (removed, added)
+    let mut number = 0;
+    let mut hunk_left = (0, 0);
+
+    for line in output.lines() {
+        if hunk_left.0 + hunk_left.1 > 0 {
+            if line.starts_with('-') {
+                hunk_left.0 -= 1;
+            } else if let Some(content) = line.strip_prefix('+') {
+                hunk_left.1 -= 1;
+                added_lines.push(AddedLine {
+                    path: path.clone(),
+                    number,
+                    content: content.to_string(),
+                });
+                number += 1;
+            }
+            continue;
+        }
+
+        if let Some(added_path) = line.strip_prefix("+++ ") {
+            path = added_path
+                .strip_prefix("b/")
+                .unwrap_or(added_path)
+                .to_string();
+        } else if line.starts_with("@@ ") {
+            hunk_left = history::parse_hunk_header(line);
+            number = line
+                .split(' ')
+                .nth(2)
+                .and_then(|x| x.strip_prefix('+'))
+                .and_then(|x| x.split(',').next())
+                .and_then(|x| x.parse().ok())
+                .unwrap_or(0);
+        }
+    }
+    added_lines
+}
+
+/// Classifies the lines of the staged version of each file with added lines, so that lines
+/// inside of block comments or embedded regions opened on earlier lines are recognized
+fn staged_regions(
+    directory: &Path,
+    added_lines: &[AddedLine],
+) -> io::Result<HashMap<String, Vec<LineRegion>>> {
+    let mut paths = added_lines
+        .iter()
+        .map(|x| x.path.clone())
+        .collect::<Vec<String>>();
+    paths.dedup();
+    let contents = git::read_tree_texts(directory, &TreeSource::Index, &paths)?;
+
+    Ok(paths
+        .into_iter()
+        .zip(contents)
+        .filter_map(|(path, contents)| {
+            let lines = contents?
+                .lines()
+                .map(|x| x.to_string())
+                .collect::<Vec<String>>();
+            let regions = language::classify_lines(Language::from_path(&path), &lines);
+            Some((path, regions))
+        })
+        .collect())
+}
+
+/// The comment in a staged line, from its first comment marker on (the whole line when it
+/// continues a block comment), `None` if the line isn't a comment according to its `region`
+fn comment_text<'a>(region: &LineRegion, content: &'a str) -> Option<&'a str> {
+    if !region.is_comment {
+        return None;
+    }
+    let syntax = region.language.comment_syntax();
+    let start = syntax
+        .line
+        .iter()
+        .chain(syntax.block.iter().map(|x| &x.0))
+        .filter_map(|x| content.find(x))
+        .min()
+        .unwrap_or(0);
+    Some(&content[start..])
+}
+
+/// Checks if the `TODO` in a comment is missing its message, its colon or isn't uppercase,
+/// e.g. `todo fix this`, `TODO fix this` or a bare `TODO:`
+fn is_malformed_todo(comment: &str) -> bool {
+    let start = match CommentType::Todo.whole_word_position(comment) {
+        Some(start) => start,
+        // words like `todos` aren't tags, there's nothing to check
+        None => return false,
+    };
+    if &comment[start..start + 4] != "TODO" {
+        return true;
+    }
+
+    let mut rest = &comment[start + 4..];
+    if rest.starts_with('(') {
+        rest = match rest.find(')') {
+            Some(end) if end > 1 => &rest[end + 1..],
+            _ => return true,
+        };
+    }
+    match rest.strip_prefix(':') {
+        Some(message) => message.trim().trim_end_matches("*/").trim().is_empty(),
+        None => true,
+    }
+}
+
+/// Writes a pre-commit hook running `rustler hook pre-commit` into the hooks directory of
+/// the repository `directory` is in
+///
+/// # Arguments
+///
+/// * `forbidden` - Passed on to the hook as its `--forbid` arg
+///
+/// # Returns
+///
+/// * `PathBuf` - Path of the written hook
+///
+/// # Errors
+///
+/// Returns an [`ErrorKind::AlreadyExists`](ErrorKind::AlreadyExists) error if there's a
+/// pre-commit hook that wasn't installed by rustler, which is never overwritten.
+pub fn install_pre_commit(directory: &Path, forbidden: &[CommentType]) -> io::Result<PathBuf> {
+    let output = git::run_git(directory, &["rev-parse", "--git-path", "hooks/pre-commit"])?;
+    let hook_path = directory.join(output.trim_end());
+
+    let mut open_options = OpenOptions::new();
+    open_options.write(true);
+    match fs::read_to_string(&hook_path) {
+        Ok(existing) if existing.contains(HOOK_MARKER) => {
+            open_options.truncate(true);
+        }
+        Ok(_) => {
+            return Err(io::Error::new(
+                ErrorKind::AlreadyExists,
+                format!(
+                    "{} already exists, remove it or call `rustler hook pre-commit` from it",
+                    hook_path.display()
+                ),
+            ))
+        }
+        Err(error) if error.kind() == ErrorKind::NotFound => {
+            // fails if another hook shows up in the meantime
+            open_options.create_new(true);
+        }
+        Err(error) => return Err(error),
+    }
+
+    if let Some(hooks_dir) = hook_path.parent() {
+        fs::create_dir_all(hooks_dir)?;
+    }
+    let forbid_arg = forbidden
+        .iter()
+        .map(|x| x.to_string().to_lowercase())
+        .collect::<Vec<String>>()
+        .join(",");
+    let mut file = open_options.open(&hook_path)?;
+    write!(
+        file,
+        "#!/bin/sh\n{}\nexec rustler hook pre-commit --forbid {}\n",
+        HOOK_MARKER, forbid_arg
+    )?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&hook_path, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(hook_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_repo;
+
+    #[test]
+    fn malformed_todos_detected() {
+        assert!(!is_malformed_todo("// TODO: fix this"));
+        assert!(!is_malformed_todo("# TODO(alice): fix this"));
+        assert!(!is_malformed_todo("/* TODO: fix this */"));
+        assert!(!is_malformed_todo("- [ ] fix this"));
+        assert!(!is_malformed_todo("// connect to Mastodon"));
+        assert!(!is_malformed_todo("// we handle todos elsewhere"));
+        assert!(is_malformed_todo("// todo: fix this"));
+        assert!(is_malformed_todo("// TODO fix this"));
+        assert!(is_malformed_todo("// TODO:"));
+        assert!(is_malformed_todo("/* TODO: */"));
+        assert!(is_malformed_todo("// TODO(): fix this"));
+    }
+
+    #[test]
+    fn added_lines_numbered_in_new_file() {
+        let output = "\
+diff --git a/a.rs b/a.rs
+--- a/a.rs
++++ b/a.rs
+@@ -2 +2,2 @@ fn a() {
+-    // old
++    // new
++    // FIXME: newer
+@@ -10,0 +12 @@
++// last
+";
+        let lines = parse_added_lines(output)
+            .into_iter()
+            .map(|x| (x.path, x.number, x.content))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![
+                ("a.rs".to_string(), 2, "    // new".to_string()),
+                ("a.rs".to_string(), 3, "    // FIXME: newer".to_string()),
+                ("a.rs".to_string(), 12, "// last".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_staged_additions_checked() {
+        let repo = test_repo::init();
+        let dir = repo.path();
+        git::run_git(dir, &["config", "diff.mnemonicPrefix", "true"]).unwrap();
+        test_repo::commit_file(
+            dir,
+            "a.rs",
+            "// FIXME: already committed\n",
+            "Alice",
+            "2020-01-10T00:00:00Z",
+        );
+        fs::write(
+            dir.join("a.rs"),
+            "// FIXME: already committed\n// XXX: new\n// TODO fix\n// TODO: fine\n\
+             // connect to Mastodon\n// we handle todos elsewhere\nlet todo = 1;\n// prefixme\n\
+             /*\n * FIXME: leaks\n */\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("c.html"),
+            "<p>FIXME in the page</p>\n<script>\n  // XXX: in the script\n</script>\n",
+        )
+        .unwrap();
+        git::run_git(dir, &["add", "a.rs", "c.html"]).unwrap();
+        fs::write(dir.join("b.rs"), "// FIXME: not staged\n").unwrap();
+
+        let violations = check_staged(dir, &[CommentType::Fixme, CommentType::XXX]).unwrap();
+
+        let summary = violations
+            .iter()
+            .map(|x| {
+                let problem = match &x.problem {
+                    Problem::Forbidden(comment_type) => comment_type.to_string(),
+                    Problem::MalformedTodo => "malformed".to_string(),
+                };
+                (x.line.path.clone(), x.line.number, problem)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                ("a.rs".to_string(), 2, "XXX".to_string()),
+                ("a.rs".to_string(), 3, "malformed".to_string()),
+                ("a.rs".to_string(), 10, "Fixme".to_string()),
+                ("c.html".to_string(), 3, "XXX".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn existing_hooks_not_clobbered() {
+        let repo = test_repo::init();
+        let dir = repo.path();
+
+        let hook_path = install_pre_commit(dir, &[CommentType::Fixme, CommentType::XXX]).unwrap();
+        let hook = fs::read_to_string(&hook_path).unwrap();
+        assert!(hook.contains("rustler hook pre-commit --forbid fixme,xxx"));
+
+        // reinstalling replaces our own hook
+        install_pre_commit(dir, &[CommentType::Fixme]).unwrap();
+        assert!(fs::read_to_string(&hook_path)
+            .unwrap()
+            .contains("--forbid fixme\n"));
+
+        fs::write(&hook_path, "#!/bin/sh\nmake lint\n").unwrap();
+        match install_pre_commit(dir, &[CommentType::Fixme]) {
+            Err(error) => assert_eq!(error.kind(), ErrorKind::AlreadyExists),
+            Ok(_) => panic!("existing hook should not be overwritten"),
+        }
+        assert_eq!(
+            fs::read_to_string(&hook_path).unwrap(),
+            "#!/bin/sh\nmake lint\n"
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use super::common_structs::{CommentType, MarkedSection};
+use super::dates::{self, SECONDS_PER_DAY};
+use super::git::{self, TreeSource};
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+/// Marks the start of each commit in the `git log` output, followed by its timestamp
+const COMMIT_MARKER: &str = "@@@rustler-commit ";
+
+/// Only commits from this many days before now count towards how often a file changes
+const CHURN_WINDOW_DAYS: i64 = 365;
+
+/// Days after which a file's recency weight drops to half, then a third, and so on
+const RECENCY_SCALE_DAYS: f64 = 30.0;
+
+/// How often and how recently a file was changed
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Churn {
+    /// Commits touching the file within the [`CHURN_WINDOW_DAYS`](CHURN_WINDOW_DAYS)
+    commits: usize,
+    /// Timestamp of the last commit touching the file
+    last_changed: i64,
+}
+
+/// A file with breadcrumbs, ranked by how worth addressing they are
+pub struct Hotspot {
+    /// Path of the file, as in the titles of its sections
+    pub path: String,
+    pub breadcrumbs: usize,
+    /// Commits touching the file in the last year
+    pub commits: usize,
+    /// Timestamp of the last commit touching the file, `None` if it was never committed
+    pub last_changed: Option<i64>,
+    pub score: f64,
+}
+
+/// Ranks the files of `sections` as hotspots and reorders the sections to match, hottest first
+///
+/// # Arguments
+///
+/// * `display_type` - Only the sections shown for the `--type` arg count towards the ranking,
+///   see [`is_displayed`](CommentType::is_displayed)
+/// * `source` - The tree the sections were read from, `None` for the working directory
+///
+/// # Notes
+///
+/// The score of a file is its breadcrumb count, multiplied by one plus its commits in the last
+/// year and weighted by how long ago it last changed (`1 / (1 + days / 30)`). Files that were
+/// never committed count as just changed, since they're being worked on.
+pub fn rank(
+    sections: &mut [MarkedSection],
+    display_type: &CommentType,
+    source: Option<&TreeSource>,
+    now: i64,
+) -> io::Result<Vec<Hotspot>> {
+    let directory = Path::new(".");
+    let root = git::repo_root(directory)?;
+    let revision = match source {
+        Some(TreeSource::Revision(revision)) => revision.as_str(),
+        _ => "HEAD",
+    };
+    let churn = file_churn(&root, revision, now)?;
+
+    let mut hotspots: Vec<Hotspot> = vec![];
+    let mut indices: HashMap<String, usize> = HashMap::new();
+    for section in sections
+        .iter()
+        .filter(|x| x.comment_type.is_displayed(display_type))
+    {
+        let idx = *indices
+            .entry(section.title_xxx.clone())
+            .or_insert_with_key(|path| {
+                let file_churn =
+                    git::repo_relative_path(path, &root, source).and_then(|x| churn.get(&x));
+                hotspots.push(Hotspot {
+                    path: path.clone(),
+                    breadcrumbs: 0,
+                    commits: file_churn.map_or(0, |x| x.commits),
+                    last_changed: file_churn.map(|x| x.last_changed),
+                    score: 0.0,
+                });
+                hotspots.len() - 1
+            });
+        hotspots[idx].breadcrumbs += 1;
+    }
+
+    for hotspot in hotspots.iter_mut() {
+        hotspot.score = score(hotspot, now);
+    }
+    hotspots.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    let ranks = hotspots
+        .iter()
+        .enumerate()
+        .map(|(i, x)| (x.path.clone(), i))
+        .collect::<HashMap<String, usize>>();
+    // hidden sections of files without displayed ones go last, they aren't shown anyway
+    sections.sort_by_key(|x| ranks.get(&x.title_xxx).copied().unwrap_or(usize::MAX));
+    Ok(hotspots)
+}
+
+fn score(hotspot: &Hotspot, now: i64) -> f64 {
+    let days = hotspot
+        .last_changed
+        .map_or(0, |x| dates::days_since(x, now));
+    let recency = 1.0 / (1.0 + days as f64 / RECENCY_SCALE_DAYS);
+    hotspot.breadcrumbs as f64 * (1 + hotspot.commits) as f64 * recency
+}
+
+/// Reads how often and how recently each file was changed in the history of `revision`
+fn file_churn(root: &Path, revision: &str, now: i64) -> io::Result<HashMap<String, Churn>> {
+    let format = format!("--format={}%at", COMMIT_MARKER);
+    let output = git::run_git(
+        root,
+        &[
+            "log",
+            "--name-only",
+            "--no-renames",
+            "--no-color",
+            &format,
+            revision,
+            "--",
+        ],
+    )?;
+    Ok(parse_log(&output, now))
+}
+
+/// Parses the output of `git log --name-only`, newest commit first
+fn parse_log(output: &str, now: i64) -> HashMap<String, Churn> {
+    let mut churn: HashMap<String, Churn> = HashMap::new();
+    let mut timestamp = 0;
+
+    for line in output.lines() {
+        if let Some(commit_time) = line.strip_prefix(COMMIT_MARKER) {
+            timestamp = commit_time.parse().unwrap_or(0);
+        } else if !line.is_empty() {
+            let is_recent = now - timestamp <= CHURN_WINDOW_DAYS * SECONDS_PER_DAY;
+            let file_churn = churn.entry(line.to_string()).or_insert(Churn {
+                commits: 0,
+                last_changed: timestamp,
+            });
+            if is_recent {
+                file_churn.commits += 1;
+            }
+        }
+    }
+    churn
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn churn_counts_recent_commits() {
+        let now = 400 * SECONDS_PER_DAY;
+        let output = format!(
+            "{m}{}\n\na.rs\nb.rs\n{m}{}\n\na.rs\n{m}{}\n\na.rs\n",
+            390 * SECONDS_PER_DAY,
+            100 * SECONDS_PER_DAY,
+            10 * SECONDS_PER_DAY,
+            m = COMMIT_MARKER
+        );
+
+        let churn = parse_log(&output, now);
+
+        assert_eq!(
+            churn["a.rs"],
+            Churn {
+                commits: 2,
+                last_changed: 390 * SECONDS_PER_DAY
+            }
+        );
+        assert_eq!(
+            churn["b.rs"],
+            Churn {
+                commits: 1,
+                last_changed: 390 * SECONDS_PER_DAY
+            }
+        );
+    }
+
+    #[test]
+    fn busy_recent_files_score_higher() {
+        let now = 1000 * SECONDS_PER_DAY;
+        let hotspot = |breadcrumbs, commits, days_ago: Option<i64>| Hotspot {
+            path: String::new(),
+            breadcrumbs,
+            commits,
+            last_changed: days_ago.map(|x| now - x * SECONDS_PER_DAY),
+            score: 0.0,
+        };
+
+        let busy = score(&hotspot(1, 50, Some(2)), now);
+        let dead = score(&hotspot(3, 1, Some(900)), now);
+        let new = score(&hotspot(1, 0, None), now);
+
+        assert!(busy > dead);
+        assert!(busy > new);
+        assert_eq!(new, 1.0);
+    }
+}
@@ -0,0 +1,458 @@
+use super::common_structs::{CommentType, OutputBlock};
+use super::dates;
+use super::formats::xml::escape;
+use super::language::CommentSyntax;
+use super::render::{Renderer, Report};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Order the groups of each directory are shown in, most pressing first
+const TYPE_ORDER: [CommentType; 4] = [
+    CommentType::XXX,
+    CommentType::Fixme,
+    CommentType::Todo,
+    CommentType::Note,
+];
+
+const STYLE: &str = "
+body { font: 14px/1.5 system-ui, sans-serif; margin: 0 auto; max-width: 70rem; padding: 1rem 2rem; color: #1f2328; }
+h2 { border-bottom: 1px solid #d0d7de; font-size: 1.2rem; margin-top: 2rem; }
+h3 { font-size: 1rem; margin: 1rem 0 .5rem; }
+code, pre { font: 12px/1.45 ui-monospace, monospace; }
+.summary span { margin-right: 1rem; }
+.filters { display: flex; gap: .5rem; margin: 1rem 0; position: sticky; top: 0; background: #fff; padding: .5rem 0; }
+.filters input { flex: 1; }
+.crumb { border: 1px solid #d0d7de; border-radius: 6px; margin: .25rem 0; padding: .25rem .75rem; }
+.crumb summary { cursor: pointer; }
+.meta { color: #59636e; margin: .25rem 0; }
+.tag { border-radius: 4px; color: #fff; font-size: 11px; font-weight: 600; padding: 0 .4rem; }
+.t-xxx { background: #cf222e; }
+.t-fixme { background: #bc4c00; }
+.t-todo { background: #0969da; }
+.t-note { background: #59636e; }
+pre { background: #f6f8fa; border-radius: 6px; overflow-x: auto; padding: .5rem 0; }
+pre span.line { display: block; padding: 0 .75rem; }
+pre span.special { background: #fff8c5; }
+.ln { color: #8c959f; display: inline-block; margin-right: 1rem; text-align: right; user-select: none; width: 3rem; }
+.c { color: #6e7781; font-style: italic; }
+.s { color: #0a3069; }
+mark { background: none; color: #cf222e; font-weight: 600; }
+";
+
+const SCRIPT: &str = "
+(function () {
+  var search = document.getElementById('search');
+  var filters = ['type', 'author', 'owner'].map(function (id) {
+    return document.getElementById(id);
+  });
+  var crumbs = Array.prototype.slice.call(document.querySelectorAll('.crumb'));
+  function matches(crumb) {
+    var query = search.value.toLowerCase();
+    if (query && crumb.textContent.toLowerCase().indexOf(query) < 0) {
+      return false;
+    }
+    var values = [[crumb.dataset.type], [crumb.dataset.author], crumb.dataset.owners.split(' ')];
+    return filters.every(function (filter, i) {
+      return !filter || !filter.value || values[i].indexOf(filter.value) >= 0;
+    });
+  }
+  function update() {
+    var shown = 0;
+    crumbs.forEach(function (crumb) {
+      crumb.hidden = !matches(crumb);
+      shown += crumb.hidden ? 0 : 1;
+    });
+    document.querySelectorAll('.group, .dir').forEach(function (group) {
+      group.hidden = !group.querySelector('.crumb:not([hidden])');
+    });
+    document.getElementById('shown').textContent = shown;
+  }
+  search.addEventListener('input', update);
+  filters.forEach(function (filter) {
+    if (filter) {
+      filter.addEventListener('change', update);
+    }
+  });
+})();
+";
+
+/// Processes the given [`OutputBlock`s](OutputBlock) into a single self-contained HTML page,
+/// ready to write to file.
+///
+/// Breadcrumbs are grouped by directory, then by type, each with its context collapsed under it.
+/// Styles and the search and filter script are inlined, so the page works offline.
+pub fn get_html_output_str(output_blocks: Vec<OutputBlock>, now: i64) -> String {
+    let mut html = String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+    html.push_str("<meta charset=\"utf-8\">\n<title>Breadcrumbs</title>\n");
+    html.push_str(&format!("<style>{}</style>\n</head>\n<body>\n", STYLE));
+    html.push_str(&format!(
+        "<h1>Breadcrumbs</h1>\n<p class=\"meta\">Generated on {}</p>\n",
+        dates::format_date(now)
+    ));
+    html.push_str(&summary(&output_blocks));
+    html.push_str(&filters(&output_blocks));
+
+    let mut directories: BTreeMap<String, Vec<OutputBlock>> = BTreeMap::new();
+    for block in output_blocks {
+        directories
+            .entry(directory_of(&block.file))
+            .or_default()
+            .push(block);
+    }
+    for (directory, blocks) in directories {
+        html.push_str(&format!(
+            "<section class=\"dir\">\n<h2>{} ({})</h2>\n",
+            escape(&directory),
+            blocks.len()
+        ));
+        for comment_type in TYPE_ORDER.iter() {
+            let group = blocks
+                .iter()
+                .filter(|x| &x.block_type == comment_type)
+                .collect::<Vec<&OutputBlock>>();
+            if group.is_empty() {
+                continue;
+            }
+            html.push_str(&format!(
+                "<div class=\"group\">\n<h3>{} ({})</h3>\n",
+                tag_of(comment_type),
+                group.len()
+            ));
+            for block in group {
+                html.push_str(&breadcrumb(block, now));
+            }
+            html.push_str("</div>\n");
+        }
+        html.push_str("</section>\n");
+    }
+
+    html.push_str(&format!("<script>{}</script>\n</body>\n</html>\n", SCRIPT));
+    html
+}
+
+/// The HTML report, of all the special lines whatever their type like the Markdown export
+pub struct Html;
+
+impl Renderer for Html {
+    fn render(&self, report: &Report, out: &mut dyn Write) -> io::Result<()> {
+        let output_blocks = report
+            .sections
+            .iter()
+            .map(OutputBlock::from_marked_section)
+            .collect();
+        write!(out, "{}", get_html_output_str(output_blocks, report.now))
+    }
+}
+
+/// Directory a file is in, `.` for files at the top of the scan
+fn directory_of(file: &str) -> String {
+    match Path::new(file.trim_start_matches("./")).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.display().to_string(),
+        _ => ".".to_string(),
+    }
+}
+
+fn tag_of(comment_type: &CommentType) -> &'static str {
+    comment_type.tag().unwrap_or_default()
+}
+
+/// Total and per type counts, the total being updated as filters are applied
+fn summary(blocks: &[OutputBlock]) -> String {
+    let mut html = format!(
+        "<p class=\"summary\"><span><strong id=\"shown\">{0}</strong> of {0} shown</span>",
+        blocks.len()
+    );
+    for comment_type in TYPE_ORDER.iter() {
+        let count = blocks
+            .iter()
+            .filter(|x| &x.block_type == comment_type)
+            .count();
+        html.push_str(&format!("<span>{} {}</span>", count, tag_of(comment_type)));
+    }
+    html.push_str("</p>\n");
+    html
+}
+
+/// The search box, and a select for each of type, author and owner that has any values
+fn filters(blocks: &[OutputBlock]) -> String {
+    let types = TYPE_ORDER
+        .iter()
+        .filter(|x| blocks.iter().any(|block| &block.block_type == *x))
+        .map(tag_of)
+        .collect::<Vec<&str>>();
+    let authors = blocks
+        .iter()
+        .filter_map(|x| x.blame.as_ref().map(|blame| blame.author.as_str()))
+        .collect::<BTreeSet<&str>>();
+    let owners = blocks
+        .iter()
+        .flat_map(|x| x.owners.iter().map(|owner| owner.as_str()))
+        .collect::<BTreeSet<&str>>();
+
+    let mut html = String::from(
+        "<div class=\"filters\">\n<input id=\"search\" type=\"search\" placeholder=\"Search\">\n",
+    );
+    for (id, label, values) in [
+        ("type", "All types", types),
+        ("author", "All authors", authors.into_iter().collect()),
+        ("owner", "All owners", owners.into_iter().collect()),
+    ] {
+        if values.is_empty() {
+            continue;
+        }
+        html.push_str(&format!(
+            "<select id=\"{}\"><option value=\"\">{}</option>",
+            id, label
+        ));
+        for value in values {
+            html.push_str(&format!("<option>{}</option>", escape(value)));
+        }
+        html.push_str("</select>\n");
+    }
+    html.push_str("</div>\n");
+    html
+}
+
+fn breadcrumb(block: &OutputBlock, now: i64) -> String {
+    let tag = tag_of(&block.block_type);
+    let author = block.blame.as_ref().map_or("", |x| x.author.as_str());
+    let cell_label = match block.cell {
+        Some(cell) => format!(" (cell #{})", cell),
+        None => String::new(),
+    };
+    let mut html = format!(
+        "<details class=\"crumb\" data-type=\"{}\" data-author=\"{}\" data-owners=\"{}\">\n",
+        tag,
+        escape(author),
+        escape(&block.owners.join(" "))
+    );
+    html.push_str(&format!(
+        "<summary><span class=\"tag t-{}\">{}</span> <code>{}:{}</code>{} {}</summary>\n",
+        tag.to_lowercase(),
+        tag,
+        escape(&block.file),
+        block.special_line.line_number,
+        cell_label,
+        escape(block.special_line.tokenized_line.join(" ").trim())
+    ));
+
+    if let Some(blame) = &block.blame {
+        html.push_str(&format!(
+            "<p class=\"meta\">Added by {} &lt;{}&gt; in <code>{}</code> on {} ({} days ago)</p>\n",
+            escape(&blame.author),
+            escape(&blame.email),
+            escape(blame.short_commit()),
+            blame.date(),
+            blame.age_days(now)
+        ));
+    }
+    if !block.owners.is_empty() {
+        html.push_str(&format!(
+            "<p class=\"meta\">Owned by {}</p>\n",
+            escape(&block.owners.join(", "))
+        ));
+    }
+    if block.embedded {
+        html.push_str(&format!(
+            "<p class=\"meta\">Embedded {}</p>\n",
+            block.language
+        ));
+    }
+
+    let syntax = block.language.comment_syntax();
+    let mut open_block = None;
+    html.push_str("<pre><code>");
+    for line in &block.all_lines {
+        let class = if line.line_number == block.special_line.line_number {
+            "line special"
+        } else {
+            "line"
+        };
+        html.push_str(&format!(
+            "<span class=\"{}\"><span class=\"ln\">{}</span>{}</span>",
+            class,
+            line.line_number,
+            highlight(&line.tokenized_line.join(" "), syntax, &mut open_block)
+        ));
+    }
+    html.push_str("</code></pre>\n</details>\n");
+    html
+}
+
+/// What starts next in a line being highlighted
+enum Token {
+    LineComment,
+    BlockComment(&'static str, &'static str),
+    String,
+}
+
+/// Highlights the comments (and the tags in them) and double quoted strings of a line,
+/// carrying a block comment left open into `open_block` for the following lines
+fn highlight(line: &str, syntax: &CommentSyntax, open_block: &mut Option<&'static str>) -> String {
+    let mut html = String::new();
+    let mut rest = line;
+
+    while !rest.is_empty() {
+        if let Some(closer) = *open_block {
+            let end = match rest.find(closer) {
+                Some(start) => {
+                    *open_block = None;
+                    start + closer.len()
+                }
+                None => rest.len(),
+            };
+            html.push_str(&comment(&rest[..end]));
+            rest = &rest[end..];
+            continue;
+        }
+
+        // comment markers win over a quote at the same position, e.g. Python's `"""`
+        let mut next: Option<(usize, Token)> = None;
+        let mut consider = |position: Option<usize>, token: Token| {
+            if let Some(position) = position {
+                if next.as_ref().is_none_or(|(x, _)| position < *x) {
+                    next = Some((position, token));
+                }
+            }
+        };
+        for marker in syntax.line {
+            consider(rest.find(marker), Token::LineComment);
+        }
+        for (opener, closer) in syntax.block {
+            consider(rest.find(opener), Token::BlockComment(opener, closer));
+        }
+        consider(rest.find('"'), Token::String);
+
+        let (start, token) = match next {
+            Some(next) => next,
+            None => {
+                html.push_str(&escape(rest));
+                break;
+            }
+        };
+        html.push_str(&escape(&rest[..start]));
+        rest = &rest[start..];
+        match token {
+            Token::LineComment => {
+                html.push_str(&comment(rest));
+                break;
+            }
+            Token::BlockComment(opener, closer) => {
+                *open_block = Some(closer);
+                html.push_str(&comment(opener));
+                rest = &rest[opener.len()..];
+            }
+            Token::String => {
+                let end = string_end(rest);
+                html.push_str(&format!(
+                    "<span class=\"s\">{}</span>",
+                    escape(&rest[..end])
+                ));
+                rest = &rest[end..];
+            }
+        }
+    }
+    html
+}
+
+/// Length of the double quoted string `text` starts with, up to the end of the line if unclosed
+fn string_end(text: &str) -> usize {
+    let mut is_escaped = false;
+    for (i, x) in text.char_indices().skip(1) {
+        match x {
+            '\\' => is_escaped = !is_escaped,
+            '"' if !is_escaped => return i + 1,
+            _ => is_escaped = false,
+        }
+    }
+    text.len()
+}
+
+/// A comment span, with its tags marked
+fn comment(text: &str) -> String {
+    let mut html = String::from("<span class=\"c\">");
+    let mut word_start = None;
+    for (i, x) in text
+        .char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+    {
+        let is_word = x.is_alphanumeric() || x == '_';
+        match word_start {
+            None if is_word => word_start = Some(i),
+            Some(start) if !is_word => {
+                let word = &text[start..i];
+                if TYPE_ORDER.iter().any(|x| x.tag() == Some(word)) {
+                    html.push_str(&format!("<mark>{}</mark>", word));
+                } else {
+                    html.push_str(&escape(word));
+                }
+                word_start = None;
+            }
+            _ => {}
+        }
+        if !is_word && i < text.len() {
+            html.push_str(&escape(&text[i..i + x.len_utf8()]));
+        }
+    }
+    html.push_str("</span>");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::fixtures::section;
+    use crate::language::Language;
+
+    #[test]
+    fn comments_and_strings_highlighted() {
+        let syntax = Language::Rust.comment_syntax();
+        let mut open_block = None;
+
+        assert_eq!(
+            highlight(
+                "let a = \"// \\\"b\"; // TODO: <c>",
+                syntax,
+                &mut open_block
+            ),
+            "let a = <span class=\"s\">&quot;// \\&quot;b&quot;</span>; \
+             <span class=\"c\">// <mark>TODO</mark>: &lt;c&gt;</span>"
+        );
+        assert_eq!(
+            highlight("a /* FIXMES", syntax, &mut open_block),
+            "a <span class=\"c\">/*</span><span class=\"c\"> FIXMES</span>"
+        );
+        assert_eq!(
+            highlight("XXX */ b", syntax, &mut open_block),
+            "<span class=\"c\"><mark>XXX</mark> */</span> b"
+        );
+        assert_eq!(open_block, None);
+    }
+
+    #[test]
+    fn breadcrumbs_grouped_by_directory_and_type() {
+        let mut fixme = section();
+        fixme.comment_type = CommentType::Fixme;
+        fixme.lines[1].content = "    // FIXME: <script>".to_string();
+        let mut top_level = section();
+        top_level.title_xxx = "./build.rs".to_string();
+        let blocks = [section(), fixme, top_level]
+            .iter()
+            .map(OutputBlock::from_marked_section)
+            .collect();
+
+        let html = get_html_output_str(blocks, 1_600_000_000);
+
+        let position = |text: &str| html.find(text).unwrap();
+        assert!(position("<h2>. (1)</h2>") < position("<h2>src (2)</h2>"));
+        assert!(position("<h3>FIXME (1)</h3>") < html.rfind("<h3>TODO (1)</h3>").unwrap());
+        assert!(html.contains(
+            "<details class=\"crumb\" data-type=\"FIXME\" data-author=\"Alice\" data-owners=\"@org/core\">"
+        ));
+        assert!(html.contains("<option>@org/core</option>"));
+        assert!(html.contains("&lt;script&gt;"));
+        // everything is inlined, nothing is fetched
+        assert!(!html.contains("src=") && !html.contains("href="));
+    }
+}
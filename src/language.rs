@@ -0,0 +1,665 @@
+use std::fmt;
+use std::path::Path;
+
+/// The comment delimiters used by a single language
+pub struct CommentSyntax {
+    /// Markers that comment out the rest of the line (e.g. `//`, `#`)
+    pub line: &'static [&'static str],
+    /// Opening and closing markers of block comments (e.g. `/*` and `*/`)
+    pub block: &'static [(&'static str, &'static str)],
+}
+
+const C_STYLE: CommentSyntax = CommentSyntax {
+    line: &["//"],
+    block: &[("/*", "*/")],
+};
+const HASH_STYLE: CommentSyntax = CommentSyntax {
+    line: &["#"],
+    block: &[],
+};
+const PYTHON_STYLE: CommentSyntax = CommentSyntax {
+    line: &["#"],
+    block: &[(r#"""""#, r#"""""#), ("'''", "'''")],
+};
+const CSS_STYLE: CommentSyntax = CommentSyntax {
+    line: &[],
+    block: &[("/*", "*/")],
+};
+const SGML_STYLE: CommentSyntax = CommentSyntax {
+    line: &[],
+    block: &[("<!--", "-->")],
+};
+const ORG_STYLE: CommentSyntax = CommentSyntax {
+    line: &["# "],
+    block: &[("#+BEGIN_COMMENT", "#+END_COMMENT")],
+};
+const SQL_STYLE: CommentSyntax = CommentSyntax {
+    line: &["--"],
+    block: &[("/*", "*/")],
+};
+const LUA_STYLE: CommentSyntax = CommentSyntax {
+    line: &["--"],
+    block: &[("--[[", "]]")],
+};
+const JINJA_STYLE: CommentSyntax = CommentSyntax {
+    line: &[],
+    block: &[("{#", "#}"), ("<!--", "-->")],
+};
+const ERB_STYLE: CommentSyntax = CommentSyntax {
+    line: &[],
+    block: &[("<%#", "%>"), ("<!--", "-->")],
+};
+const HANDLEBARS_STYLE: CommentSyntax = CommentSyntax {
+    line: &[],
+    block: &[("{{!--", "--}}"), ("{{!", "}}"), ("<!--", "-->")],
+};
+const GO_TEMPLATE_STYLE: CommentSyntax = CommentSyntax {
+    line: &[],
+    block: &[("{{/*", "*/}}"), ("{{- /*", "*/ -}}"), ("<!--", "-->")],
+};
+/// Used when the language of a file can't be determined, matches any of the common markers
+const GENERIC_STYLE: CommentSyntax = CommentSyntax {
+    line: &["//", "#", "/**", r#"""""#],
+    block: &[],
+};
+
+/// Represents the languages (and in turn, the comment syntaxes) that rustler
+/// knows how to scan.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Language {
+    C,
+    Cpp,
+    CSharp,
+    Css,
+    Erb,
+    Go,
+    GoTemplate,
+    Handlebars,
+    Html,
+    Java,
+    JavaScript,
+    Jinja,
+    Kotlin,
+    Lua,
+    Markdown,
+    Org,
+    Python,
+    Ruby,
+    Rust,
+    Scss,
+    Shell,
+    Sql,
+    Svelte,
+    Swift,
+    Toml,
+    TypeScript,
+    Vue,
+    Xml,
+    Yaml,
+    Unknown,
+}
+
+impl Language {
+    /// Returns the language for either a file extension (`"rs"`) or a language
+    /// name as used in code fence info strings and `lang` attributes (`"rust"`)
+    ///
+    /// # Note
+    ///
+    /// If no match is found, will return [`Language::Unknown`](Self::Unknown).
+    pub fn from_name(name: &str) -> Self {
+        match name.to_lowercase().as_str() {
+            "c" | "h" => Self::C,
+            "cpp" | "cc" | "cxx" | "hpp" | "c++" => Self::Cpp,
+            "cs" | "csharp" | "c#" => Self::CSharp,
+            "css" => Self::Css,
+            "erb" | "rhtml" => Self::Erb,
+            "go" | "golang" => Self::Go,
+            "tmpl" | "gotmpl" | "gohtml" | "go-template" => Self::GoTemplate,
+            "hbs" | "handlebars" | "mustache" => Self::Handlebars,
+            "html" | "htm" | "xhtml" => Self::Html,
+            "java" => Self::Java,
+            "j2" | "jinja" | "jinja2" | "twig" | "njk" => Self::Jinja,
+            "js" | "jsx" | "mjs" | "cjs" | "javascript" => Self::JavaScript,
+            "kt" | "kts" | "kotlin" => Self::Kotlin,
+            "lua" => Self::Lua,
+            "md" | "markdown" => Self::Markdown,
+            "org" => Self::Org,
+            "py" | "python" | "python3" => Self::Python,
+            "rb" | "ruby" => Self::Ruby,
+            "rs" | "rust" => Self::Rust,
+            "scss" | "sass" | "less" => Self::Scss,
+            "sh" | "bash" | "zsh" | "shell" | "console" => Self::Shell,
+            "sql" => Self::Sql,
+            "svelte" => Self::Svelte,
+            "swift" => Self::Swift,
+            "toml" => Self::Toml,
+            "ts" | "tsx" | "typescript" => Self::TypeScript,
+            "vue" => Self::Vue,
+            "xml" | "svg" => Self::Xml,
+            "yml" | "yaml" => Self::Yaml,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Returns the language of the file at `path` based on its extension
+    pub fn from_path(path: &str) -> Self {
+        Path::new(path)
+            .extension()
+            .and_then(|x| x.to_str())
+            .map_or(Self::Unknown, Self::from_name)
+    }
+
+    /// Lowercase name of the language, as used for Markdown code fences
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::C => "c",
+            Self::Cpp => "cpp",
+            Self::CSharp => "csharp",
+            Self::Css => "css",
+            Self::Erb => "erb",
+            Self::Go => "go",
+            Self::GoTemplate => "go-template",
+            Self::Handlebars => "handlebars",
+            Self::Html => "html",
+            Self::Java => "java",
+            Self::JavaScript => "javascript",
+            Self::Jinja => "jinja",
+            Self::Kotlin => "kotlin",
+            Self::Lua => "lua",
+            Self::Markdown => "markdown",
+            Self::Org => "org",
+            Self::Python => "python",
+            Self::Ruby => "ruby",
+            Self::Rust => "rust",
+            Self::Scss => "scss",
+            Self::Shell => "shell",
+            Self::Sql => "sql",
+            Self::Svelte => "svelte",
+            Self::Swift => "swift",
+            Self::Toml => "toml",
+            Self::TypeScript => "typescript",
+            Self::Vue => "vue",
+            Self::Xml => "xml",
+            Self::Yaml => "yaml",
+            Self::Unknown => "",
+        }
+    }
+
+    pub fn comment_syntax(&self) -> &'static CommentSyntax {
+        match self {
+            Self::C
+            | Self::Cpp
+            | Self::CSharp
+            | Self::Go
+            | Self::Java
+            | Self::JavaScript
+            | Self::Kotlin
+            | Self::Rust
+            | Self::Scss
+            | Self::Swift
+            | Self::TypeScript => &C_STYLE,
+            Self::Ruby | Self::Shell | Self::Toml | Self::Yaml => &HASH_STYLE,
+            Self::Python => &PYTHON_STYLE,
+            Self::Css => &CSS_STYLE,
+            Self::Html | Self::Markdown | Self::Svelte | Self::Vue | Self::Xml => &SGML_STYLE,
+            Self::Org => &ORG_STYLE,
+            Self::Sql => &SQL_STYLE,
+            Self::Lua => &LUA_STYLE,
+            Self::Jinja => &JINJA_STYLE,
+            Self::Erb => &ERB_STYLE,
+            Self::Handlebars => &HANDLEBARS_STYLE,
+            Self::GoTemplate => &GO_TEMPLATE_STYLE,
+            Self::Unknown => &GENERIC_STYLE,
+        }
+    }
+
+    /// Whether files of this language can contain regions written in other languages
+    fn embeds_languages(&self) -> bool {
+        matches!(
+            self,
+            Self::Erb
+                | Self::GoTemplate
+                | Self::Handlebars
+                | Self::Html
+                | Self::Jinja
+                | Self::Markdown
+                | Self::Org
+                | Self::Svelte
+                | Self::Vue
+        )
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Unknown => write!(f, "unknown"),
+            _ => write!(f, "{}", self.name()),
+        }
+    }
+}
+
+/// The language and comment state of a single line in a file
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct LineRegion {
+    pub language: Language,
+    pub is_comment: bool,
+}
+
+/// Walks the lines of a file written in `host` language and works out which
+/// language each line is written in, and whether it is (part of) a comment.
+///
+/// Files that embed other languages are split into regions so that, e.g.:
+///
+/// * `<script>` and `<style>` blocks in HTML, Vue and Svelte files use JS and CSS comments
+/// * fenced code blocks in Markdown use the comment syntax of the fence's info string
+/// * `#+BEGIN_SRC <lang>` blocks in Org files use the comment syntax of `<lang>`
+///
+/// # Returns
+///
+/// One [`LineRegion`](LineRegion) per line passed in
+pub fn classify_lines(host: Language, lines: &[String]) -> Vec<LineRegion> {
+    let mut regions = Vec::with_capacity(lines.len());
+    let mut embedded: Option<Embedded> = None;
+    let mut open_block: Option<&'static str> = None;
+
+    for line in lines {
+        if host.embeds_languages() {
+            if let Some(current) = &embedded {
+                if current.is_closed_by(line) {
+                    embedded = None;
+                    open_block = None;
+                    regions.push(LineRegion {
+                        language: host,
+                        is_comment: false,
+                    });
+                    continue;
+                }
+            } else if let Some(opened) = Embedded::opened_by(host, line) {
+                embedded = Some(opened);
+                open_block = None;
+                regions.push(LineRegion {
+                    language: host,
+                    is_comment: false,
+                });
+                continue;
+            }
+        }
+
+        let language = embedded.as_ref().map_or(host, |x| x.language);
+        let is_comment = scan_comment_state(line, language.comment_syntax(), &mut open_block);
+        regions.push(LineRegion {
+            language,
+            is_comment,
+        });
+    }
+    regions
+}
+
+/// Checks if `line` contains a comment, carrying over any block comment left
+/// open at the end of the line into `open_block` for the following lines.
+fn scan_comment_state(
+    line: &str,
+    syntax: &CommentSyntax,
+    open_block: &mut Option<&'static str>,
+) -> bool {
+    let mut is_comment = open_block.is_some();
+    let mut rest = line;
+
+    loop {
+        if let Some(closer) = *open_block {
+            match rest.find(closer) {
+                Some(idx) => {
+                    rest = &rest[idx + closer.len()..];
+                    *open_block = None;
+                }
+                None => break,
+            }
+        }
+
+        let line_comment_pos = syntax.line.iter().filter_map(|x| rest.find(x)).min();
+        let block_comment = syntax
+            .block
+            .iter()
+            .filter_map(|(opener, closer)| rest.find(opener).map(|idx| (idx, *opener, *closer)))
+            .min_by_key(|x| x.0);
+
+        match (line_comment_pos, block_comment) {
+            (Some(line_pos), Some((block_pos, _, _))) if line_pos <= block_pos => {
+                return true;
+            }
+            (Some(_), None) => return true,
+            (_, Some((block_pos, opener, closer))) => {
+                is_comment = true;
+                rest = &rest[block_pos + opener.len()..];
+                *open_block = Some(closer);
+            }
+            (None, None) => break,
+        }
+    }
+    is_comment
+}
+
+/// An embedded region that is currently open, along with how it gets closed
+struct Embedded {
+    language: Language,
+    closing_tag: &'static str,
+}
+
+impl Embedded {
+    fn opened_by(host: Language, line: &str) -> Option<Self> {
+        let trimmed = line.trim_start();
+        if host == Language::Markdown {
+            // fences can also open inside of a list item
+            let trimmed = ["- ", "* ", "+ "]
+                .iter()
+                .find_map(|x| trimmed.strip_prefix(x))
+                .unwrap_or(trimmed);
+            let fence = ["```", "~~~"].iter().find(|x| trimmed.starts_with(*x))?;
+            let info = trimmed
+                .trim_start_matches(['`', '~'])
+                .trim_start()
+                .trim_start_matches('{')
+                .split(|x: char| x.is_whitespace() || x == ',' || x == '}')
+                .next()
+                .unwrap_or("");
+            return Some(Self {
+                language: Language::from_name(info.trim_start_matches('.')),
+                closing_tag: fence,
+            });
+        }
+
+        let lower = trimmed.to_lowercase();
+        if host == Language::Org {
+            let info = lower.strip_prefix("#+begin_src")?;
+            return Some(Self {
+                language: Language::from_name(info.split_whitespace().next().unwrap_or("")),
+                closing_tag: "#+end_src",
+            });
+        }
+
+        let (tag, closing_tag, default_language) = if lower.starts_with("<script") {
+            ("<script", "</script>", Language::JavaScript)
+        } else if lower.starts_with("<style") {
+            ("<style", "</style>", Language::Css)
+        } else {
+            return None;
+        };
+
+        // single line elements never open a region
+        if lower.contains(closing_tag) {
+            return None;
+        }
+
+        let language = lang_attribute(&lower[tag.len()..])
+            .map(Language::from_name)
+            .filter(|x| *x != Language::Unknown)
+            .unwrap_or(default_language);
+        Some(Self {
+            language,
+            closing_tag,
+        })
+    }
+
+    fn is_closed_by(&self, line: &str) -> bool {
+        let trimmed = line.trim_start();
+        if self.closing_tag.starts_with(['`', '~']) {
+            trimmed.starts_with(self.closing_tag)
+                && trimmed.trim_start_matches(['`', '~']).trim().is_empty()
+        } else {
+            trimmed.to_lowercase().starts_with(self.closing_tag)
+        }
+    }
+}
+
+/// Reads the value of a `lang="..."` (or `type="text/..."`) attribute out of an opening tag
+fn lang_attribute(tag_attributes: &str) -> Option<&str> {
+    for attribute in &["lang=", "type="] {
+        if let Some(idx) = tag_attributes.find(attribute) {
+            let value = tag_attributes[idx + attribute.len()..]
+                .trim_start_matches(['"', '\''])
+                .split(['"', '\'', ' ', '>'])
+                .next()?;
+            return Some(value.rsplit('/').next().unwrap_or(value));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_lines(text: &str) -> Vec<String> {
+        text.lines().map(|x| x.to_string()).collect()
+    }
+
+    fn languages_of(regions: &[LineRegion]) -> Vec<Language> {
+        regions.iter().map(|x| x.language).collect()
+    }
+
+    fn comments_of(regions: &[LineRegion]) -> Vec<bool> {
+        regions.iter().map(|x| x.is_comment).collect()
+    }
+
+    mod detection {
+        use super::*;
+
+        #[test]
+        fn language_detected_from_extension() {
+            assert_eq!(Language::from_path("src/main.rs"), Language::Rust);
+            assert_eq!(Language::from_path("App.vue"), Language::Vue);
+            assert_eq!(Language::from_path("README"), Language::Unknown);
+        }
+
+        #[test]
+        fn template_language_detected_from_last_extension() {
+            assert_eq!(Language::from_path("views/index.html.erb"), Language::Erb);
+            assert_eq!(Language::from_path("base.html.j2"), Language::Jinja);
+            assert_eq!(Language::from_path("card.hbs"), Language::Handlebars);
+            assert_eq!(Language::from_path("page.tmpl"), Language::GoTemplate);
+        }
+
+        #[test]
+        fn unknown_language_keeps_generic_comment_symbols() {
+            let regions = classify_lines(Language::Unknown, &to_lines("# todo\n// todo\nplain"));
+            assert_eq!(comments_of(&regions), vec![true, true, false]);
+        }
+
+        #[test]
+        fn multi_line_block_comment_is_tracked() {
+            let source = "let x = 1; /* start\n   TODO: inside\n end */ let y = 2;\nlet z = 3;";
+            let regions = classify_lines(Language::Rust, &to_lines(source));
+            assert_eq!(comments_of(&regions), vec![true, true, true, false]);
+        }
+
+        #[test]
+        fn line_comment_before_block_opener_does_not_open_block() {
+            let regions = classify_lines(Language::Rust, &to_lines("// see /* here\nlet x = 1;"));
+            assert_eq!(comments_of(&regions), vec![true, false]);
+        }
+    }
+
+    mod templates {
+        use super::*;
+
+        #[test]
+        fn jinja_comments_detected() {
+            let regions = classify_lines(
+                Language::Jinja,
+                &to_lines(
+                    "{# TODO: jinja #}
+{{ todo.title }}
+{#
+  FIXME: multi
+#}",
+                ),
+            );
+            assert_eq!(comments_of(&regions), vec![true, false, true, true, true]);
+        }
+
+        #[test]
+        fn erb_comments_detected_but_not_output_tags() {
+            let regions = classify_lines(
+                Language::Erb,
+                &to_lines(
+                    "<%# TODO: erb %>
+<%= todo_count %>
+<!-- NOTE: html -->",
+                ),
+            );
+            assert_eq!(comments_of(&regions), vec![true, false, true]);
+        }
+
+        #[test]
+        fn handlebars_comments_detected() {
+            let regions = classify_lines(
+                Language::Handlebars,
+                &to_lines(
+                    "{{!-- TODO: long form --}}
+{{! TODO: short form }}
+{{todo}}",
+                ),
+            );
+            assert_eq!(comments_of(&regions), vec![true, true, false]);
+        }
+
+        #[test]
+        fn go_template_comments_detected() {
+            let regions = classify_lines(
+                Language::GoTemplate,
+                &to_lines(
+                    "{{/* TODO: go */}}
+{{- /* FIXME: trimmed */ -}}
+{{ .Todo }}",
+                ),
+            );
+            assert_eq!(comments_of(&regions), vec![true, true, false]);
+        }
+
+        #[test]
+        fn template_script_blocks_use_js_syntax() {
+            let regions = classify_lines(
+                Language::Jinja,
+                &to_lines(
+                    "<script>
+// TODO: js
+</script>",
+                ),
+            );
+            assert_eq!(
+                languages_of(&regions),
+                vec![Language::Jinja, Language::JavaScript, Language::Jinja]
+            );
+            assert_eq!(comments_of(&regions), vec![false, true, false]);
+        }
+    }
+
+    mod embedded {
+        use super::*;
+
+        #[test]
+        fn org_source_blocks_use_block_language() {
+            let source = "* Notes\n#+BEGIN_SRC python\n# TODO: py\n#+END_SRC\n# NOTE: org comment";
+            let regions = classify_lines(Language::Org, &to_lines(source));
+            assert_eq!(
+                languages_of(&regions),
+                vec![
+                    Language::Org,
+                    Language::Org,
+                    Language::Python,
+                    Language::Org,
+                    Language::Org
+                ]
+            );
+            assert_eq!(comments_of(&regions), vec![false, false, true, false, true]);
+        }
+
+        #[test]
+        fn vue_script_and_style_regions_switch_syntax() {
+            let source = "<template>\n<!-- TODO: html -->\n</template>\n<script lang=\"ts\">\n// TODO: ts\n</script>\n<style>\n/* TODO: css */\n</style>";
+            let regions = classify_lines(Language::Vue, &to_lines(source));
+            assert_eq!(
+                languages_of(&regions),
+                vec![
+                    Language::Vue,
+                    Language::Vue,
+                    Language::Vue,
+                    Language::Vue,
+                    Language::TypeScript,
+                    Language::Vue,
+                    Language::Vue,
+                    Language::Css,
+                    Language::Vue,
+                ]
+            );
+            assert_eq!(
+                comments_of(&regions),
+                vec![false, true, false, false, true, false, false, true, false]
+            );
+        }
+
+        #[test]
+        fn html_comment_syntax_not_used_inside_script() {
+            let source = "<script>\nconst html = \"<!-- TODO -->\";\n</script>";
+            let regions = classify_lines(Language::Html, &to_lines(source));
+            assert_eq!(comments_of(&regions), vec![false, false, false]);
+        }
+
+        #[test]
+        fn single_line_script_does_not_open_region() {
+            let source = "<script src=\"app.js\"></script>\n# TODO: not a comment in html";
+            let regions = classify_lines(Language::Html, &to_lines(source));
+            assert_eq!(languages_of(&regions), vec![Language::Html, Language::Html]);
+            assert_eq!(comments_of(&regions), vec![false, false]);
+        }
+
+        #[test]
+        fn markdown_fences_use_info_string_language() {
+            let source =
+                "# Title with todo\n```python\n# TODO: py\n```\n~~~ {.rust}\n// FIXME: rs\n~~~";
+            let regions = classify_lines(Language::Markdown, &to_lines(source));
+            assert_eq!(
+                languages_of(&regions),
+                vec![
+                    Language::Markdown,
+                    Language::Markdown,
+                    Language::Python,
+                    Language::Markdown,
+                    Language::Markdown,
+                    Language::Rust,
+                    Language::Markdown,
+                ]
+            );
+            assert_eq!(
+                comments_of(&regions),
+                vec![false, false, true, false, false, true, false]
+            );
+        }
+
+        #[test]
+        fn markdown_fence_opened_in_list_item() {
+            let source = "- ```python\n\t# TODO: py\n\t```\n# heading";
+            let regions = classify_lines(Language::Markdown, &to_lines(source));
+            assert_eq!(
+                languages_of(&regions),
+                vec![
+                    Language::Markdown,
+                    Language::Python,
+                    Language::Markdown,
+                    Language::Markdown
+                ]
+            );
+        }
+
+        #[test]
+        fn markdown_fence_with_info_string_does_not_close_region() {
+            let source = "```\n```rust\n```";
+            let regions = classify_lines(Language::Markdown, &to_lines(source));
+            assert_eq!(
+                languages_of(&regions),
+                vec![Language::Markdown, Language::Unknown, Language::Markdown]
+            );
+        }
+    }
+}
@@ -1,18 +1,42 @@
 mod cli;
+mod codeowners;
+mod commit_log;
 mod common_structs;
+mod dates;
+mod diff;
 mod file_io;
+mod formats;
+mod git;
+mod history;
+mod hook;
+mod hotspot;
+mod html_report;
+mod language;
 mod markdown;
+mod markup_formatter;
+mod notebook;
 mod output_formatter;
 mod parser;
+mod policy;
 mod printer;
+mod render;
+mod task_list;
+mod template;
 
 use ansi_term::{self, Colour};
-use std::ffi::OsStr;
 use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
 use std::path::Path;
+use std::process;
 
-use common_structs::MarkedSection;
+use cli::{CommandLineArgs, HookAction, Output, OutputFormat, Subcommand};
+use common_structs::{CommentType, MarkedSection, ParseData, ScanOptions};
+use git::TreeSource;
+use hotspot::Hotspot;
+use policy::StalePolicy;
 use printer::ConsolePrinter;
+use render::{Renderer, Report};
 
 fn setup_ansi_colors() {
     #[cfg(target_os = "windows")]
@@ -25,37 +49,402 @@ fn main() {
 
     // the `cli_args` struct returned here has all of the pre-validated
     // CLI args, opts, inputs, etc.
-    let cli_args = cli::CommandLineArgs::new();
+    let cli_args = CommandLineArgs::new();
 
-    // `file_lines` a vec of all of the non-empty lines (Strings) in the file
-    let parsed_data = match parser::read_data_for_path(&cli_args.input_path) {
-        Ok(data) => data,
-        Err(error) => raise_io_error(error),
+    match &cli_args.subcommand {
+        Some(Subcommand::Diff {
+            base,
+            head,
+            merge_base,
+        }) => run_diff(&cli_args, base, head, *merge_base),
+        Some(Subcommand::History { range }) => run_history(&cli_args, range),
+        Some(Subcommand::Log { range }) => run_log(&cli_args, range),
+        Some(Subcommand::Hook { action, forbidden }) => match action {
+            HookAction::PreCommit => run_pre_commit(forbidden),
+            HookAction::Install => run_hook_install(forbidden),
+        },
+        None => run_scan(cli_args),
+    }
+}
+
+/// A directory inside of the git repository of the scanned path: the path itself if it's a
+/// directory, else the directory of the file
+///
+/// Paths in a git tree are looked up from the current directory, like `git ls-files` does.
+fn repo_directory<'a>(input_path: &'a str, source: Option<&TreeSource>) -> &'a Path {
+    let path = Path::new(input_path);
+    match path.parent() {
+        _ if source.is_some() => Path::new("."),
+        _ if path.is_dir() => path,
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+/// Scans the input file or directory and shows its special lines
+fn run_scan(cli_args: CommandLineArgs) {
+    let now = dates::now_timestamp();
+    let source = cli_args.tree_source.as_ref();
+
+    // owners are looked up from the CODEOWNERS file of the scanned git repository, if requested
+    let owners = if cli_args.show_owners {
+        let directory = repo_directory(&cli_args.input_path, source);
+        match (
+            codeowners::find(directory, source),
+            git::repo_root(directory),
+        ) {
+            (Ok(Some(code_owners)), Ok(root)) => Some((code_owners, root)),
+            (Ok(None), _) => None,
+            (Err(error), _) | (_, Err(error)) => raise_git_error(error),
+        }
+    } else {
+        None
     };
 
-    // from `file_lines` we make the vec of context-aware "code patches" here
-    let code_patch = MarkedSection::unpack_lines(parsed_data, cli_args.context);
+    // line based formats are written as soon as each file is scanned, unless the order needs the whole scan
+    let mut outputs = open_outputs(&cli_args, !cli_args.sort_by_age && !cli_args.rank_hotspot);
 
-    // creating printer and consuming it to display terminal output
-    let printer = ConsolePrinter::new(Colour::Purple);
-    printer.print_all_lines(&code_patch, cli_args.display_type);
-
-    // output to markdown if export flag is set
-    if cli_args.markdown_output_flag {
-        let file_extension = Path::new(&cli_args.input_path)
-            .extension()
-            .and_then(OsStr::to_str);
-        let output_filename = cli_args.output_filename.unwrap();
-        if let Err(error) = file_io::export_marked_sections_to_markdown_file(
-            code_patch,
-            file_extension,
-            &output_filename,
+    let mut code_patch = vec![];
+    let mut scan = |parsed_data: ParseData| {
+        // from the file lines we make the vec of context-aware "code patches" here
+        let mut sections = MarkedSection::unpack_lines(parsed_data, &scan_options(&cli_args));
+
+        // blame info is looked up from the local git repository, if requested
+        if cli_args.blame {
+            git::attach_blame(&mut sections, source);
+        }
+        if let Some((code_owners, root)) = &owners {
+            codeowners::attach_owners(&mut sections, code_owners, root, source);
+        }
+        if cli_args.show_owners && !cli_args.owner_filter.is_empty() {
+            codeowners::retain_owned_by(&mut sections, &cli_args.owner_filter);
+        }
+        if let Some(min_age_days) = cli_args.older_than_days {
+            policy::retain_older_than(&mut sections, min_age_days, now);
+        }
+
+        write_reports(
+            &mut outputs,
+            &scan_report(&cli_args, &sections, now, None),
+            true,
+        );
+        code_patch.extend(sections);
+    };
+    match source {
+        Some(source) => match parser::read_data_for_path_in_tree(&cli_args.input_path, source) {
+            Ok(data) => scan(data),
+            Err(error) => raise_git_error(error),
+        },
+        None => {
+            if let Err(error) = parser::stream_data_for_path(&cli_args.input_path, &mut scan) {
+                raise_io_error(error)
+            }
+        }
+    }
+
+    if cli_args.sort_by_age {
+        policy::sort_by_age(&mut code_patch, now);
+    }
+    let hotspots = if cli_args.rank_hotspot {
+        match hotspot::rank(
+            &mut code_patch,
+            &cli_args.display_type,
+            cli_args.tree_source.as_ref(),
+            now,
         ) {
+            Ok(hotspots) => Some(hotspots),
+            Err(error) => raise_git_error(error),
+        }
+    } else {
+        None
+    };
+
+    let stale_count = if cli_args.fail_on_stale {
+        stale_policy(&cli_args)
+            .find_violations(&code_patch, now)
+            .len()
+    } else {
+        0
+    };
+
+    let report = scan_report(&cli_args, &code_patch, now, hotspots.as_deref());
+    write_reports(&mut outputs, &report, false);
+
+    if stale_count > 0 {
+        eprintln!(
+            "{}: {} FIXME(s) older than {} days",
+            Colour::Red.paint("STALE"),
+            stale_count,
+            cli_args.stale_age_days
+        );
+        process::exit(1);
+    }
+}
+
+/// Compares the special lines of two trees of the git repository in the current directory
+fn run_diff(cli_args: &CommandLineArgs, base: &TreeSource, head: &TreeSource, merge_base: bool) {
+    let base = match (base, head) {
+        (TreeSource::Revision(base), TreeSource::Revision(head)) if merge_base => {
+            match git::merge_base(Path::new("."), base, head) {
+                Ok(merge_base) => TreeSource::Revision(merge_base),
+                Err(error) => raise_git_error(error),
+            }
+        }
+        _ => base.clone(),
+    };
+    let report = match diff::diff_trees(Path::new("."), &base, head, &scan_options(cli_args)) {
+        Ok(report) => report,
+        Err(error) => raise_git_error(error),
+    };
+
+    let printer = ConsolePrinter::new(Colour::Purple);
+    if let Err(error) =
+        printer.print_diff(&report, cli_args.display_type.clone(), &mut io::stdout())
+    {
+        raise_output_error(error)
+    }
+
+    if let Some(output_filename) = &cli_args.output_filename {
+        if let Err(error) = file_io::export_diff_to_markdown_file(report, output_filename) {
+            raise_io_error(error)
+        }
+    }
+}
+
+/// Walks the git history of the repository in the current directory for the lifecycle of each special line
+fn run_history(cli_args: &CommandLineArgs, range: &str) {
+    let report = match history::walk_history(Path::new("."), range, &scan_options(cli_args)) {
+        Ok(report) => report,
+        Err(error) => raise_git_error(error),
+    };
+
+    let printer = ConsolePrinter::new(Colour::Purple);
+    if let Err(error) =
+        printer.print_history(&report, cli_args.display_type.clone(), &mut io::stdout())
+    {
+        raise_output_error(error)
+    }
+
+    if let Some(output_filename) = &cli_args.output_filename {
+        if let Err(error) = file_io::export_history_to_markdown_file(report, output_filename) {
             raise_io_error(error)
         }
     }
 }
 
+/// Scans the commit messages of the git repository in the current directory for special lines
+fn run_log(cli_args: &CommandLineArgs, range: &str) {
+    let sections =
+        match commit_log::scan_commit_messages(Path::new("."), range, &scan_options(cli_args)) {
+            Ok(sections) => sections,
+            Err(error) => raise_git_error(error),
+        };
+
+    let report = scan_report(cli_args, &sections, dates::now_timestamp(), None);
+    write_reports(&mut open_outputs(cli_args, false), &report, false);
+}
+
+/// Checks the staged changes of the git repository in the current directory, exits with an error on any problem
+fn run_pre_commit(forbidden: &[CommentType]) {
+    let violations = match hook::check_staged(Path::new("."), forbidden) {
+        Ok(violations) => violations,
+        Err(error) => raise_git_error(error),
+    };
+
+    for violation in &violations {
+        let problem = match &violation.problem {
+            hook::Problem::Forbidden(comment_type) => {
+                format!("{} is not allowed", comment_type.to_string().to_uppercase())
+            }
+            hook::Problem::MalformedTodo => {
+                "TODO should be written as `TODO: message` or `TODO(owner): message`".to_string()
+            }
+        };
+        eprintln!(
+            "{}:{}: {}\n\t{}",
+            violation.line.path,
+            violation.line.number,
+            problem,
+            violation.line.content.trim()
+        );
+    }
+
+    if !violations.is_empty() {
+        eprintln!(
+            "{}: {} problem(s) in the staged changes",
+            Colour::Red.paint("REJECTED"),
+            violations.len()
+        );
+        process::exit(1);
+    }
+}
+
+/// Installs the pre-commit hook into the git repository in the current directory
+fn run_hook_install(forbidden: &[CommentType]) {
+    match hook::install_pre_commit(Path::new("."), forbidden) {
+        Ok(hook_path) => println!("Installed pre-commit hook at {}", hook_path.display()),
+        Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
+            clap::Error::with_description(&error.to_string(), clap::ErrorKind::InvalidValue).exit()
+        }
+        Err(error) => raise_git_error(error),
+    }
+}
+
+/// An output picked with `--format`, ready to be written to
+struct OpenOutput {
+    renderer: Box<dyn Renderer>,
+    out: Box<dyn Write>,
+    /// Set if the output is written a report per file, as soon as each file is scanned
+    is_streaming: bool,
+    /// Set once the consumer of stdout (e.g. `head`) has all it wanted
+    is_closed: bool,
+}
+
+/// Creates the files of the outputs and their renderers, exits with an error if a file can't be created
+///
+/// # Arguments
+///
+/// * `can_stream` - Whether line based formats can be written as soon as each file is scanned
+fn open_outputs(cli_args: &CommandLineArgs, can_stream: bool) -> Vec<OpenOutput> {
+    cli_args
+        .outputs
+        .iter()
+        .map(|output| {
+            let out: Box<dyn Write> = match &output.path {
+                Some(path) => match File::create(path) {
+                    Ok(file) => Box::new(BufWriter::new(file)),
+                    Err(error) => raise_io_error(format!("{}: {}", path, error)),
+                },
+                None => Box::new(io::stdout()),
+            };
+            OpenOutput {
+                renderer: renderer(cli_args, output),
+                out,
+                is_streaming: can_stream && output.format.is_line_based(),
+                is_closed: false,
+            }
+        })
+        .collect()
+}
+
+fn renderer(cli_args: &CommandLineArgs, output: &Output) -> Box<dyn Renderer> {
+    match output.format {
+        // colours would end up as escape codes in a file
+        OutputFormat::Console if output.path.is_some() => Box::new(ConsolePrinter::plain()),
+        OutputFormat::Console => Box::new(ConsolePrinter::new(Colour::Purple)),
+        OutputFormat::Markdown => Box::new(output_formatter::Markdown),
+        OutputFormat::Html => Box::new(html_report::Html),
+        OutputFormat::Org => Box::new(markup_formatter::Org),
+        OutputFormat::AsciiDoc => Box::new(markup_formatter::AsciiDoc),
+        OutputFormat::Rst => Box::new(markup_formatter::Rst),
+        OutputFormat::TodoTxt => Box::new(formats::todo_txt::TodoTxt),
+        OutputFormat::Taskwarrior => Box::new(formats::taskwarrior::Taskwarrior),
+        OutputFormat::Json => Box::new(formats::json::Json),
+        OutputFormat::Ndjson => Box::new(formats::ndjson::Ndjson),
+        OutputFormat::Sarif => Box::new(formats::sarif::Sarif),
+        OutputFormat::Table(dialect) => Box::new(formats::csv::Table {
+            columns: cli_args.columns.clone(),
+            dialect,
+        }),
+        OutputFormat::Grep(style) => Box::new(formats::grep::Grep(style)),
+        OutputFormat::Checkstyle => Box::new(formats::checkstyle::Checkstyle),
+        OutputFormat::Junit => Box::new(formats::junit::Junit {
+            policies: vec![stale_policy(cli_args)],
+        }),
+        OutputFormat::Github => Box::new(formats::github::Github),
+        OutputFormat::Gitlab => Box::new(formats::gitlab::Gitlab),
+        OutputFormat::Template => {
+            // only set up with a template file, see `CommandLineArgs::outputs`
+            let path = cli_args.template.as_ref().unwrap();
+            let source = match fs::read_to_string(path) {
+                Ok(source) => source,
+                Err(error) => raise_io_error(format!("{}: {}", path, error)),
+            };
+            match template::Template::parse(&source) {
+                Ok(template) => Box::new(template),
+                Err(error) => clap::Error::with_description(
+                    &format!("Invalid template {}, {}", path, error),
+                    clap::ErrorKind::InvalidValue,
+                )
+                .exit(),
+            }
+        }
+    }
+}
+
+fn scan_report<'a>(
+    cli_args: &'a CommandLineArgs,
+    sections: &'a [MarkedSection],
+    now: i64,
+    hotspots: Option<&'a [Hotspot]>,
+) -> Report<'a> {
+    Report {
+        sections,
+        display_type: cli_args.display_type.clone(),
+        root: &cli_args.input_path,
+        now,
+        hotspots,
+        show_owners: cli_args.show_owners,
+        tree_source: cli_args.tree_source.as_ref(),
+    }
+}
+
+/// Renders the report into the outputs that are (or aren't) streaming, flushing them so
+/// streamed reports show up right away
+///
+/// Stdout getting closed early doesn't stop the files from being written, the scan only stops
+/// once every output is closed.
+fn write_reports(outputs: &mut [OpenOutput], report: &Report, streaming: bool) {
+    for output in outputs
+        .iter_mut()
+        .filter(|x| x.is_streaming == streaming && !x.is_closed)
+    {
+        let result = output
+            .renderer
+            .render(report, &mut output.out)
+            .and_then(|_| output.out.flush());
+        match result {
+            Err(error) if error.kind() == io::ErrorKind::BrokenPipe => output.is_closed = true,
+            Err(error) => raise_io_error(error),
+            Ok(()) => {}
+        }
+    }
+    if outputs.iter().all(|x| x.is_closed) {
+        process::exit(0);
+    }
+}
+
+/// The policy behind `--fail-on-stale`
+fn stale_policy(cli_args: &CommandLineArgs) -> StalePolicy {
+    StalePolicy {
+        comment_type: CommentType::Fixme,
+        max_age_days: cli_args.stale_age_days,
+    }
+}
+
+fn scan_options(cli_args: &CommandLineArgs) -> ScanOptions {
+    ScanOptions {
+        context: cli_args.context,
+        include_checked_tasks: cli_args.include_checked_tasks,
+    }
+}
+
+fn raise_git_error<T: fmt::Display>(error: T) -> ! {
+    clap::Error::with_description(
+        &format!("Error reading from the git repository: {}", error),
+        clap::ErrorKind::Io,
+    )
+    .exit()
+}
+
+/// Exits quietly if the consumer of stdout (e.g. `head`) has all it wanted, with an error otherwise
+fn raise_output_error(error: io::Error) -> ! {
+    if error.kind() == io::ErrorKind::BrokenPipe {
+        process::exit(0);
+    }
+    raise_io_error(error)
+}
+
 fn raise_io_error<T: fmt::Display>(error: T) -> ! {
     clap::Error::with_description(
         &format!(
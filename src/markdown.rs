@@ -9,7 +9,7 @@ pub struct MarkdownBuilder {
 }
 
 #[allow(dead_code)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy)]
 pub enum HeaderLevel {
     H1,
     H2,
@@ -76,14 +76,13 @@ impl MarkdownBuilder {
     ///
     /// Note: Use the [`newline()`](Self.newline()) method if multi-line text is necessary.
     pub fn insert_single_line(mut self, text: &str) -> BuilderResult<Self> {
-        if let Err(error) = Self::check_single_line_text_input_for_forbidden_characters(text) {
-            return Err(error);
-        }
+        Self::check_single_line_text_input_for_forbidden_characters(text)?;
         self.contents.push_str(text);
         Ok(self)
     }
 
     /// Finishes the builder pattern by consuming `Self` and returning the final string
+    #[allow(clippy::wrong_self_convention)]
     pub fn to_markdown_string(self) -> String {
         self.contents
     }
@@ -134,7 +133,7 @@ mod tests {
             let builder = get_empty_builder();
             let text_to_insert = "test text";
             let export_string = builder
-                .insert_single_line(&text_to_insert)
+                .insert_single_line(text_to_insert)
                 .expect("valid text should not return Err")
                 .to_markdown_string();
 
@@ -148,7 +147,7 @@ mod tests {
         fn multi_line_text_returns_error() {
             let builder = get_empty_builder();
             let text_to_insert = "multiline \n text";
-            let insert_result = builder.insert_single_line(&text_to_insert);
+            let insert_result = builder.insert_single_line(text_to_insert);
 
             assert!(
                 insert_result.is_err(),
@@ -167,7 +166,7 @@ mod tests {
         fn indented_text_returns_error() {
             let builder = get_empty_builder();
             let text_to_insert = "indented \t text";
-            let insert_result = builder.insert_single_line(&text_to_insert);
+            let insert_result = builder.insert_single_line(text_to_insert);
 
             assert!(
                 insert_result.is_err(),
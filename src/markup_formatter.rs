@@ -0,0 +1,260 @@
+use super::common_structs::{CommentType, OutputBlock};
+use super::output_formatter::{blame_text, context_lines, context_text, owners_text};
+use super::render::{Renderer, Report};
+use std::io::{self, Write};
+
+/// The special line of a block, as the title of its entry
+fn title(block: &OutputBlock) -> String {
+    block
+        .special_line
+        .tokenized_line
+        .join(" ")
+        .trim()
+        .to_string()
+}
+
+fn tag(block: &OutputBlock) -> &'static str {
+    block.block_type.tag().unwrap_or_default()
+}
+
+/// Org mode export, a `* TODO` heading per special line with its location as properties
+pub struct Org;
+
+impl Renderer for Org {
+    fn render(&self, report: &Report, out: &mut dyn Write) -> io::Result<()> {
+        for block in report.sections.iter().map(OutputBlock::from_marked_section) {
+            write!(out, "{}", org_entry(&block))?;
+        }
+        Ok(())
+    }
+}
+
+fn org_code(text: &str) -> String {
+    format!("={}=", text)
+}
+
+fn org_entry(block: &OutputBlock) -> String {
+    // `XXX` and `FIXME` get the higher priorities, the rest keep the default one
+    let priority = match block.block_type {
+        CommentType::XXX => "[#A] ",
+        CommentType::Fixme => "[#B] ",
+        _ => "",
+    };
+    let mut entry = format!("* TODO {}{} :{}:\n", priority, title(block), tag(block));
+    entry.push_str("  :PROPERTIES:\n");
+    entry.push_str(&format!("  :FILE:     {}\n", block.file));
+    entry.push_str(&format!(
+        "  :LINE:     {}\n",
+        block.special_line.line_number
+    ));
+    if let Some(cell) = block.cell {
+        entry.push_str(&format!("  :CELL:     {}\n", cell));
+    }
+    if let Some(blame) = &block.blame {
+        entry.push_str(&format!("  :AUTHOR:   {}\n", blame.author));
+        entry.push_str(&format!("  :COMMIT:   {}\n", blame.commit));
+    }
+    if !block.owners.is_empty() {
+        entry.push_str(&format!("  :OWNERS:   {}\n", block.owners.join(" ")));
+    }
+    entry.push_str("  :END:\n");
+
+    for text in [blame_text(block, org_code), owners_text(block, org_code)]
+        .iter()
+        .flatten()
+    {
+        entry.push_str(&format!("  - {}\n", text));
+    }
+    let lines = context_lines(block);
+    if !lines.is_empty() {
+        entry.push_str(&format!("  {}:\n", context_text(block, org_code)));
+        entry.push_str(&format!("  #+BEGIN_SRC {}\n", block.language.name()));
+        for line in lines {
+            // lines that Org would read as headings or keywords are escaped with a comma
+            let escape = if line.starts_with('*') || line.trim_start().starts_with("#+") {
+                ","
+            } else {
+                ""
+            };
+            entry.push_str(&format!("  {}{}\n", escape, line));
+        }
+        entry.push_str("  #+END_SRC\n");
+    }
+    entry
+}
+
+/// AsciiDoc export, a checklist item per special line with its context as a source block
+pub struct AsciiDoc;
+
+impl Renderer for AsciiDoc {
+    fn render(&self, report: &Report, out: &mut dyn Write) -> io::Result<()> {
+        for block in report.sections.iter().map(OutputBlock::from_marked_section) {
+            write!(out, "{}", asciidoc_item(&block))?;
+        }
+        Ok(())
+    }
+}
+
+fn asciidoc_code(text: &str) -> String {
+    format!("`+{}+`", text)
+}
+
+fn asciidoc_item(block: &OutputBlock) -> String {
+    // passed through as is, so the comment's own markup isn't read as AsciiDoc
+    let mut item = format!(
+        "* [ ] pass:c[{}] ({})\n",
+        title(block).replace(']', "\\]"),
+        asciidoc_code(&format!(
+            "{}:{}",
+            block.file, block.special_line.line_number
+        ))
+    );
+    for text in [
+        blame_text(block, asciidoc_code),
+        owners_text(block, asciidoc_code),
+    ]
+    .iter()
+    .flatten()
+    {
+        item.push_str(&format!("+\n{}\n", text));
+    }
+    let lines = context_lines(block);
+    if !lines.is_empty() {
+        item.push_str(&format!("+\n.{}\n", context_text(block, asciidoc_code)));
+        match block.language.name() {
+            "" => item.push_str("[source]\n"),
+            language => item.push_str(&format!("[source,{}]\n", language)),
+        }
+        item.push_str("----\n");
+        for line in lines {
+            item.push_str(&format!("{}\n", line));
+        }
+        item.push_str("----\n");
+    }
+    item.push('\n');
+    item
+}
+
+/// reStructuredText export, a list item per special line with its context in a `code-block`
+pub struct Rst;
+
+impl Renderer for Rst {
+    fn render(&self, report: &Report, out: &mut dyn Write) -> io::Result<()> {
+        for block in report.sections.iter().map(OutputBlock::from_marked_section) {
+            write!(out, "{}", rst_item(&block))?;
+        }
+        Ok(())
+    }
+}
+
+fn rst_code(text: &str) -> String {
+    format!("``{}``", text)
+}
+
+/// Escapes the characters reStructuredText reads as inline markup
+fn rst_escape(text: &str) -> String {
+    text.chars()
+        .flat_map(|x| {
+            let escape = ['\\', '*', '`', '_', '|'].contains(&x).then_some('\\');
+            escape.into_iter().chain(std::iter::once(x))
+        })
+        .collect()
+}
+
+fn rst_item(block: &OutputBlock) -> String {
+    let mut item = format!(
+        "- [ ] {} ({})\n",
+        rst_escape(&title(block)),
+        rst_code(&format!(
+            "{}:{}",
+            block.file, block.special_line.line_number
+        ))
+    );
+    for text in [blame_text(block, rst_code), owners_text(block, rst_code)]
+        .iter()
+        .flatten()
+    {
+        item.push_str(&format!("\n  {}\n", text));
+    }
+    let lines = context_lines(block);
+    if !lines.is_empty() {
+        let language = match block.language.name() {
+            "" => "text",
+            language => language,
+        };
+        item.push_str(&format!("\n  {}:\n", context_text(block, rst_code)));
+        item.push_str(&format!("\n  .. code-block:: {}\n\n", language));
+        for line in lines {
+            item.push_str(&format!("     {}\n", line));
+        }
+    }
+    item.push('\n');
+    item
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::fixtures::section;
+
+    fn block(comment_type: CommentType, special_line: &str) -> OutputBlock {
+        let mut section = section();
+        section.comment_type = comment_type;
+        section.blame = None;
+        section.lines[1].content = special_line.to_string();
+        section.lines[2].content = "* not a heading".to_string();
+        OutputBlock::from_marked_section(&section)
+    }
+
+    #[test]
+    fn org_headings_with_properties() {
+        assert_eq!(
+            org_entry(&block(CommentType::Fixme, "    // FIXME: leaks")),
+            "* TODO [#B] // FIXME: leaks :FIXME:\n  \
+             :PROPERTIES:\n  \
+             :FILE:     src/main.rs\n  \
+             :LINE:     5\n  \
+             :OWNERS:   @org/core\n  \
+             :END:\n  \
+             - Owned by =@org/core=\n  \
+             Context for =FIXME= in =line #5=:\n  \
+             #+BEGIN_SRC rust\n  \
+             fn main() {\n      \
+             // FIXME: leaks\n  \
+             ,* not a heading\n  \
+             #+END_SRC\n"
+        );
+    }
+
+    #[test]
+    fn asciidoc_checklist_with_source_block() {
+        assert_eq!(
+            asciidoc_item(&block(CommentType::Todo, "// TODO: *[x]*")),
+            "* [ ] pass:c[// TODO: *[x\\]*] (`+src/main.rs:5+`)\n\
+             +\n\
+             Owned by `+@org/core+`\n\
+             +\n\
+             .Context for `+TODO+` in `+line #5+`\n\
+             [source,rust]\n\
+             ----\n\
+             fn main() {\n\
+             // TODO: *[x]*\n\
+             * not a heading\n\
+             ----\n\n"
+        );
+    }
+
+    #[test]
+    fn rst_list_with_code_block() {
+        assert_eq!(
+            rst_item(&block(CommentType::Note, "// NOTE: see `foo_bar`")),
+            "- [ ] // NOTE: see \\`foo\\_bar\\` (``src/main.rs:5``)\n\
+             \n  Owned by ``@org/core``\n\
+             \n  Context for ``NOTE`` in ``line #5``:\n\
+             \n  .. code-block:: rust\n\n     \
+             fn main() {\n     \
+             // NOTE: see `foo_bar`\n     \
+             * not a heading\n\n"
+        );
+    }
+}
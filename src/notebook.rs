@@ -0,0 +1,133 @@
+use super::common_structs::{ParsedFile, ParsedNotebook};
+use super::language::Language;
+use serde_json::Value;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::Path;
+
+/// Checks if the file at `path` is a Jupyter notebook, based on its extension
+pub fn is_notebook(path: &Path) -> bool {
+    path.extension().and_then(|x| x.to_str()) == Some("ipynb")
+}
+
+/// Reads the notebook at `path`, returning one [`ParsedFile`](ParsedFile) per code or markdown cell
+///
+/// Code cells are scanned with the comment syntax of the kernel's language, markdown
+/// cells are treated as Markdown. Raw cells are skipped, but still counted so that the
+/// cell numbers match the ones shown in Jupyter.
+///
+/// # Errors
+///
+/// Returns an [`ErrorKind::InvalidData`](ErrorKind::InvalidData) error if the file isn't a valid notebook.
+pub fn read_notebook(path: &Path) -> io::Result<ParsedNotebook> {
+    let contents = fs::read_to_string(path)?;
+    let filename = path.as_os_str().to_str().unwrap_or_default().to_string();
+    parse_notebook(&filename, &contents)
+}
+
+/// Same as [`read_notebook`](read_notebook), for notebook contents that are already in memory
+pub fn parse_notebook(filename: &str, contents: &str) -> io::Result<ParsedNotebook> {
+    let notebook: Value = serde_json::from_str(contents)
+        .map_err(|error| io::Error::new(ErrorKind::InvalidData, error))?;
+    let raw_cells = notebook["cells"].as_array().ok_or_else(|| {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            format!("{} is not a notebook: no `cells` array", filename),
+        )
+    })?;
+    let kernel_language = kernel_language(&notebook);
+
+    let cells = raw_cells
+        .iter()
+        .enumerate()
+        .filter_map(|(i, cell)| {
+            let language = match cell["cell_type"].as_str()? {
+                "code" => kernel_language,
+                "markdown" => Language::Markdown,
+                _ => return None,
+            };
+            Some(ParsedFile {
+                filename: filename.to_string(),
+                lines: cell_source_lines(&cell["source"]),
+                language,
+                cell: Some(i + 1),
+            })
+        })
+        .collect();
+
+    Ok(ParsedNotebook { cells })
+}
+
+fn kernel_language(notebook: &Value) -> Language {
+    let metadata = &notebook["metadata"];
+    metadata["kernelspec"]["language"]
+        .as_str()
+        .or_else(|| metadata["language_info"]["name"].as_str())
+        .map_or(Language::Python, Language::from_name)
+}
+
+/// Cell sources are stored either as one string or as a list of lines (each ending with `\n`)
+fn cell_source_lines(source: &Value) -> Vec<String> {
+    let text = match source {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts.iter().filter_map(|x| x.as_str()).collect(),
+        _ => String::new(),
+    };
+    text.lines().map(|x| x.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common_structs::{MarkedSection, ParseData, ScanOptions};
+
+    const NOTEBOOK: &str = r##"{
+        "metadata": {"kernelspec": {"language": "python", "name": "python3"}},
+        "cells": [
+            {"cell_type": "markdown", "source": ["# Title\n", "TODO: explain the model\n", "FIXME: update the todo list\n"]},
+            {"cell_type": "raw", "source": "ignored"},
+            {"cell_type": "code", "source": "import numpy\n# FIXME: seed\nx = 1"}
+        ]
+    }"##;
+
+    #[test]
+    fn cells_read_with_their_languages_and_numbers() {
+        let notebook = parse_notebook("nb.ipynb", NOTEBOOK).expect("notebook should parse");
+
+        assert_eq!(notebook.cells.len(), 2, "raw cells should be skipped");
+        assert_eq!(notebook.cells[0].cell, Some(1));
+        assert_eq!(notebook.cells[0].language, Language::Markdown);
+        assert_eq!(
+            notebook.cells[0].lines,
+            vec![
+                "# Title",
+                "TODO: explain the model",
+                "FIXME: update the todo list"
+            ]
+        );
+        assert_eq!(notebook.cells[1].cell, Some(3));
+        assert_eq!(notebook.cells[1].language, Language::Python);
+        assert_eq!(notebook.cells[1].lines[1], "# FIXME: seed");
+    }
+
+    #[test]
+    fn markdown_cells_typed_by_their_marker() {
+        let notebook = parse_notebook("nb.ipynb", NOTEBOOK).expect("notebook should parse");
+        let sections =
+            MarkedSection::unpack_lines(ParseData::Notebook(notebook), &ScanOptions::default());
+
+        let types = sections
+            .iter()
+            .map(|x| x.comment_type.to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(types, ["Todo", "Fixme", "Fixme"]);
+    }
+
+    #[test]
+    fn invalid_notebook_returns_invalid_data_error() {
+        match parse_notebook("nb.ipynb", "{\"nbformat\": 4}") {
+            Err(error) => assert_eq!(error.kind(), ErrorKind::InvalidData),
+            Ok(_) => panic!("notebook without cells should not parse"),
+        }
+    }
+}
@@ -1,30 +1,129 @@
-use super::common_structs::OutputBlock;
+use super::common_structs::{BlameInfo, OutputBlock};
+use super::dates;
+use super::diff::DiffReport;
+use super::history::HistoryReport;
 use super::markdown::{HeaderLevel, MarkdownBuilder};
+use super::render::{Renderer, Report};
+use std::io::{self, Write};
 
 /// Processes the given [`OutputBlock`s](OutputBlock) into a single markdown
 /// string, ready to write to file.
-pub fn get_markdown_output_str(
-    output_blocks: Vec<OutputBlock>,
-    file_extension: Option<&str>,
-) -> String {
+pub fn get_markdown_output_str(output_blocks: Vec<OutputBlock>) -> String {
     output_blocks
         .into_iter()
-        .map(|x| get_output_str_for_block(x, file_extension))
+        .map(get_output_str_for_block)
         .collect::<Vec<String>>()
         .join("")
 }
 
-fn get_output_str_for_block(block: OutputBlock, file_extension: Option<&str>) -> String {
+/// The Markdown export, a checklist of all the special lines whatever their type
+pub struct Markdown;
+
+impl Renderer for Markdown {
+    fn render(&self, report: &Report, out: &mut dyn Write) -> io::Result<()> {
+        let output_blocks = report
+            .sections
+            .iter()
+            .map(OutputBlock::from_marked_section)
+            .collect();
+        write!(out, "{}", get_markdown_output_str(output_blocks))
+    }
+}
+
+/// Processes a [`DiffReport`](DiffReport) into a single markdown string, with the
+/// added, removed and moved special lines each under their own header
+pub fn get_markdown_diff_str(report: DiffReport) -> String {
+    let mut output = String::new();
+    let groups = [("Added", report.added), ("Removed", report.removed)];
+    for (title, sections) in groups {
+        output.push_str(&group_header(title, sections.len()));
+        for section in sections {
+            output.push_str(&format!("`{}`\n\n", section.location()));
+            output.push_str(&get_output_str_for_block(OutputBlock::from_marked_section(
+                &section,
+            )));
+        }
+    }
+
+    output.push_str(&group_header("Moved", report.moved.len()));
+    for moved in report.moved {
+        output.push_str(&format!(
+            "`{}` -> `{}`\n\n",
+            moved.from,
+            moved.section.location()
+        ));
+        output.push_str(&get_output_str_for_block(OutputBlock::from_marked_section(
+            &moved.section,
+        )));
+    }
+    output
+}
+
+/// Processes a [`HistoryReport`](HistoryReport) into a markdown string, with a table of the
+/// lifecycle of each special line followed by a table of introduced and resolved counts per month
+pub fn get_markdown_history_str(report: HistoryReport) -> String {
+    let mut output = group_header("Breadcrumbs", report.lifecycles.len());
+    output.push_str("| File | Breadcrumb | Introduced | Resolved |\n");
+    output.push_str("| --- | --- | --- | --- |\n");
+    for lifecycle in &report.lifecycles {
+        output.push_str(&format!(
+            "| `{}` | `{}` | {} | {} |\n",
+            lifecycle.path,
+            lifecycle.text.replace('|', "\\|"),
+            describe_commit(&lifecycle.introduced, "before range"),
+            describe_commit(&lifecycle.resolved, "open"),
+        ));
+    }
+
+    let stats = report.monthly_stats();
+    output.push('\n');
+    output.push_str(&group_header("Per month", stats.len()));
+    output.push_str("| Month | Introduced | Resolved |\n");
+    output.push_str("| --- | --- | --- |\n");
+    for (month, month_stats) in stats {
+        output.push_str(&format!(
+            "| {} | {} | {} |\n",
+            month, month_stats.introduced, month_stats.resolved
+        ));
+    }
+    output
+}
+
+fn describe_commit(commit: &Option<BlameInfo>, missing: &str) -> String {
+    match commit {
+        Some(commit) => format!(
+            "{} by {} (`{}`)",
+            commit.date(),
+            commit.author,
+            commit.short_commit()
+        ),
+        None => missing.to_string(),
+    }
+}
+
+fn group_header(title: &str, count: usize) -> String {
+    MarkdownBuilder::new()
+        .header(HeaderLevel::H2)
+        .insert_single_line(&format!("{} ({})", title, count))
+        .unwrap()
+        .newline()
+        .newline()
+        .to_markdown_string()
+}
+
+fn get_output_str_for_block(block: OutputBlock) -> String {
     let mut md_builder = MarkdownBuilder::new();
 
     md_builder = header_for_output_block(md_builder, &block);
+    md_builder = blame_line(md_builder, &block);
+    md_builder = owners_line(md_builder, &block);
 
-    if block.all_lines.len() == 0 {
+    if block.all_lines.is_empty() {
         return md_builder.to_markdown_string();
     }
 
     md_builder = context_block_header(md_builder, &block);
-    md_builder = context_block_inner_code(md_builder, block, file_extension);
+    md_builder = context_block_inner_code(md_builder, block);
     md_builder.to_markdown_string()
 }
 
@@ -38,59 +137,112 @@ fn header_for_output_block(builder: MarkdownBuilder, block: &OutputBlock) -> Mar
         .newline()
 }
 
+/// Writes an inline code span in a markup language, e.g. `` `x` `` in Markdown
+pub type CodeSpan = fn(&str) -> String;
+
+fn markdown_code(text: &str) -> String {
+    format!("`{}`", text)
+}
+
+/// Who added the special line of a block and when, shared by the document exports
+pub fn blame_text(block: &OutputBlock, code: CodeSpan) -> Option<String> {
+    block.blame.as_ref().map(|blame| {
+        format!(
+            "Added by {} <{}> in {} on {} ({} days ago)",
+            blame.author,
+            blame.email,
+            code(blame.short_commit()),
+            blame.date(),
+            blame.age_days(dates::now_timestamp())
+        )
+    })
+}
+
+/// Who owns the file of a block, shared by the document exports
+pub fn owners_text(block: &OutputBlock, code: CodeSpan) -> Option<String> {
+    if block.owners.is_empty() {
+        return None;
+    }
+    let owners = block
+        .owners
+        .iter()
+        .map(|x| code(x))
+        .collect::<Vec<String>>()
+        .join(", ");
+    Some(format!("Owned by {}", owners))
+}
+
+/// Title of the context of a block, shared by the document exports
+pub fn context_text(block: &OutputBlock, code: CodeSpan) -> String {
+    let embedded_label = if block.embedded {
+        format!(" (embedded {})", code(&block.language.to_string()))
+    } else {
+        String::new()
+    };
+    let cell_label = match block.cell {
+        Some(cell) => format!("{}, ", code(&format!("cell #{}", cell))),
+        None => String::new(),
+    };
+    format!(
+        "Context for {} in {}{}{}",
+        code(&block.block_type.to_string().to_uppercase()),
+        cell_label,
+        code(&format!("line #{}", block.special_line.line_number)),
+        embedded_label
+    )
+}
+
+/// The lines of the context of a block, shared by the document exports
+pub fn context_lines(block: &OutputBlock) -> Vec<String> {
+    block
+        .all_lines
+        .iter()
+        .filter(|x| x.tokenized_line.len() > 1)
+        .map(|x| x.tokenized_line.join(" "))
+        .collect()
+}
+
+fn blame_line(builder: MarkdownBuilder, block: &OutputBlock) -> MarkdownBuilder {
+    match blame_text(block, markdown_code) {
+        Some(text) => builder
+            .insert_single_line(&format!("- {}", text))
+            .unwrap()
+            .newline(),
+        None => builder,
+    }
+}
+
+fn owners_line(builder: MarkdownBuilder, block: &OutputBlock) -> MarkdownBuilder {
+    match owners_text(block, markdown_code) {
+        Some(text) => builder
+            .insert_single_line(&format!("- {}", text))
+            .unwrap()
+            .newline(),
+        None => builder,
+    }
+}
+
 fn context_block_header(builder: MarkdownBuilder, block: &OutputBlock) -> MarkdownBuilder {
     builder
         .newline()
         .insert_single_line("- ")
         .unwrap()
         .header(HeaderLevel::H5)
-        .insert_single_line(&format!(
-            "Context for `{}` in `line #{}`",
-            block.block_type.to_string().to_uppercase(),
-            block.special_line.line_number
-        ))
+        .insert_single_line(&context_text(block, markdown_code))
         .unwrap()
         .newline()
 }
 
-fn get_code_block_name_from_extension(file_extension: Option<&str>) -> &str {
-    if file_extension.is_none() {
-        return "";
-    }
-    match file_extension.unwrap() {
-        "py" => "python",
-        "rs" => "rust",
-        "js" => "javascript",
-        "ts" => "typescript",
-        "cs" => "c#",
-        _ => "",
-    }
-}
-
-fn context_block_inner_code(
-    mut builder: MarkdownBuilder,
-    block: OutputBlock,
-    file_extension: Option<&str>,
-) -> MarkdownBuilder {
+fn context_block_inner_code(mut builder: MarkdownBuilder, block: OutputBlock) -> MarkdownBuilder {
     builder = builder
         .increase_indentation_level()
         .newline()
-        .insert_single_line(&format!(
-            "- ```{}",
-            get_code_block_name_from_extension(file_extension)
-        ))
+        .insert_single_line(&format!("- ```{}", block.language.name()))
         .unwrap()
         .increase_indentation_level()
         .newline();
 
-    let lines_in_block = block
-        .all_lines
-        .into_iter()
-        .filter(|x| x.tokenized_line.len() > 1)
-        .map(|x| x.tokenized_line.join(" "))
-        .collect::<Vec<String>>();
-
-    for line in lines_in_block {
+    for line in context_lines(&block) {
         builder = builder.insert_single_line(&line).unwrap().newline()
     }
 
@@ -104,7 +256,6 @@ fn context_block_inner_code(
 
 #[cfg(test)]
 mod tests {
-    use super::*;
 
     #[test]
     fn single_code_block_exported_correctly_to_string() {
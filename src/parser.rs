@@ -1,4 +1,6 @@
 use super::common_structs::{ParseData, ParsedDirectory, ParsedFile};
+use super::git::{self, TreeSource};
+use super::notebook;
 use std::fs::{self, DirEntry, File};
 use std::io::{self, BufRead, ErrorKind};
 use std::path::Path;
@@ -7,27 +9,76 @@ use std::path::Path;
 pub fn read_data_for_path(path: &str) -> io::Result<ParseData> {
     if Path::new(path).is_dir() {
         Ok(ParseData::Directory(read_directory_data_recursive(path)?))
+    } else if notebook::is_notebook(Path::new(path)) {
+        Ok(ParseData::Notebook(notebook::read_notebook(Path::new(
+            path,
+        ))?))
     } else {
         Ok(ParseData::File(read_file_data(path)?))
     }
 }
 
+/// Same as [`read_data_for_path`](read_data_for_path), but hands each file of a directory to
+/// `on_file` as soon as it's read, instead of walking the whole tree first
+///
+/// Stops at the first file that can't be read.
+pub fn stream_data_for_path(path: &str, on_file: &mut dyn FnMut(ParseData)) -> io::Result<()> {
+    if !Path::new(path).is_dir() {
+        on_file(read_data_for_path(path)?);
+        return Ok(());
+    }
+
+    let mut result = Ok(());
+    let mut process = |entry: &DirEntry| {
+        if result.is_err() {
+            return;
+        }
+        match get_parsed_files_for_path(&entry.path()) {
+            Some(Ok(files)) => on_file(ParseData::Directory(ParsedDirectory { files })),
+            Some(Err(error)) => result = Err(error),
+            None => {}
+        }
+    };
+    visit_dirs(Path::new(path), &mut process)?;
+    result
+}
+
 pub fn read_directory_data_recursive(directory_path: &str) -> io::Result<ParsedDirectory> {
     let files = get_parsed_files_for_dir_rec(directory_path)?;
 
-    Ok(ParsedDirectory {
-        directory_path: directory_path.to_string(),
-        files,
-    })
+    Ok(ParsedDirectory { files })
+}
+
+/// Reads the given files (relative to the repository `root`) out of a git tree instead of
+/// the working directory, see [`read_tree_files`](git::read_tree_files)
+pub fn read_data_for_tree(
+    root: &Path,
+    source: &TreeSource,
+    paths: &[String],
+) -> io::Result<ParseData> {
+    Ok(ParseData::Directory(ParsedDirectory {
+        files: git::read_tree_files(root, source, paths)?,
+    }))
+}
+
+/// Reads the files under `path` from a git revision or the index, rather than from the working directory
+///
+/// `path` is relative to the current directory, the returned filenames are relative to the
+/// root of the repository.
+pub fn read_data_for_path_in_tree(path: &str, source: &TreeSource) -> io::Result<ParseData> {
+    let directory = Path::new(".");
+    let root = git::repo_root(directory)?;
+    let paths = git::list_files(directory, source, path)?
+        .into_iter()
+        .filter(|x| !path_should_be_skipped(Path::new(x)))
+        .collect::<Vec<String>>();
+    read_data_for_tree(&root, source, &paths)
 }
 
 /// Top-level function to mask the usage of the FileParser for a simple interface
 pub fn read_file_data(file_path: &str) -> io::Result<ParsedFile> {
     let lines = get_lines_from_file(Path::new(file_path)).unwrap()?;
-    Ok(ParsedFile {
-        filename: file_path.to_string(),
-        lines,
-    })
+    Ok(ParsedFile::new(file_path.to_string(), lines))
 }
 
 fn get_parsed_files_for_dir_rec(directory_path: &str) -> io::Result<Vec<ParsedFile>> {
@@ -35,10 +86,7 @@ fn get_parsed_files_for_dir_rec(directory_path: &str) -> io::Result<Vec<ParsedFi
 
     let mut files = vec![];
     for file_result in unchecked_files {
-        files.push(ParsedFile {
-            filename: file_result.0,
-            lines: file_result.1?,
-        });
+        files.extend(file_result?);
     }
 
     Ok(files)
@@ -46,15 +94,14 @@ fn get_parsed_files_for_dir_rec(directory_path: &str) -> io::Result<Vec<ParsedFi
 
 fn get_unchecked_files_for_dir_rec(
     directory_path: &str,
-) -> io::Result<Vec<(String, io::Result<Vec<String>>)>> {
-    type UncheckedFileData = (String, io::Result<Vec<String>>);
-    let mut unchecked_files: Vec<UncheckedFileData> = vec![];
+) -> io::Result<Vec<io::Result<Vec<ParsedFile>>>> {
+    let mut unchecked_files = vec![];
     let mut process = |dir: &DirEntry| {
-        if let Some(lines) = get_lines_from_file(&dir.path()) {
-            unchecked_files.push((path_to_str(&dir.path()).to_string(), lines));
+        if let Some(files) = get_parsed_files_for_path(&dir.path()) {
+            unchecked_files.push(files);
         }
     };
-    visit_dirs(&Path::new(directory_path), &mut process)?;
+    visit_dirs(Path::new(directory_path), &mut process)?;
     Ok(unchecked_files)
 }
 
@@ -73,6 +120,22 @@ fn visit_dirs(dir: &Path, cb: &mut dyn FnMut(&DirEntry)) -> io::Result<()> {
     Ok(())
 }
 
+/// Reads a single file in a directory tree, notebooks get split up into one [`ParsedFile`](ParsedFile) per cell
+///
+/// Returns `None` for files that should be skipped (binary data, malformed notebooks)
+fn get_parsed_files_for_path(path: &Path) -> Option<io::Result<Vec<ParsedFile>>> {
+    if path.is_file() && notebook::is_notebook(path) {
+        return match notebook::read_notebook(path) {
+            Ok(parsed_notebook) => Some(Ok(parsed_notebook.cells)),
+            Err(error) if error.kind() == ErrorKind::InvalidData => None,
+            Err(error) => Some(Err(error)),
+        };
+    }
+
+    let lines = get_lines_from_file(path)?;
+    Some(lines.map(|x| vec![ParsedFile::new(path_to_str(path).to_string(), x)]))
+}
+
 fn path_to_str(path: &Path) -> &str {
     path.as_os_str().to_str().unwrap()
 }
@@ -81,8 +144,7 @@ fn path_should_be_skipped(path: &Path) -> bool {
     const FORBIDDEN_PATH_ARRAY: [&str; 4] = [".git", "target", ".config", "~"];
     FORBIDDEN_PATH_ARRAY
         .iter()
-        .map(|x| path_to_str(path).contains(x))
-        .any(|x| x)
+        .any(|x| path_to_str(path).contains(x))
 }
 
 /// Reads and returns an iterator with the line data for the given filename
@@ -93,13 +155,13 @@ fn path_should_be_skipped(path: &Path) -> bool {
 ///
 /// # Returns
 ///
-/// `Vec<String>` of all of the lines in the file
+/// `Vec<String>` of all of the lines in the file, empty lines included so that line numbers are kept intact
 fn get_lines_from_file(file_path: &Path) -> Option<io::Result<Vec<String>>> {
     if !file_path.is_file() {
         return None;
     }
 
-    let file = File::open(&file_path).ok()?;
+    let file = File::open(file_path).ok()?;
 
     let mut lines = Vec::new();
     for line_result in io::BufReader::new(file).lines() {
@@ -111,12 +173,36 @@ fn get_lines_from_file(file_path: &Path) -> Option<io::Result<Vec<String>>> {
                     return Some(Err(io_error));
                 }
             }
-            Ok(line) => {
-                if !line.is_empty() {
-                    lines.push(line)
-                }
-            }
+            Ok(line) => lines.push(line),
         }
     }
     Some(Ok(lines))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common_structs::{MarkedSection, ScanOptions};
+    use std::fs;
+
+    #[test]
+    fn line_numbers_one_based_and_count_blank_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.rs");
+        fs::write(&path, "fn main() {\n\n    // TODO: fix\n}\n").unwrap();
+
+        let file = read_file_data(path.to_str().unwrap()).unwrap();
+        let options = ScanOptions {
+            context: 1,
+            ..ScanOptions::default()
+        };
+        let sections = MarkedSection::unpack_lines(ParseData::File(file), &options);
+
+        let lines = sections[0]
+            .lines
+            .iter()
+            .map(|x| (x.number, x.content.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(lines, [(2, ""), (3, "    // TODO: fix"), (4, "}")]);
+    }
+}
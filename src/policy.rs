@@ -0,0 +1,115 @@
+use super::common_structs::{CommentType, MarkedSection};
+
+/// Fails a scan when breadcrumbs of a given type have been around for too long
+pub struct StalePolicy {
+    pub comment_type: CommentType,
+    pub max_age_days: i64,
+}
+
+impl StalePolicy {
+    /// What the policy forbids, e.g. `FIXME older than 90 days`
+    pub fn description(&self) -> String {
+        format!(
+            "{} older than {} days",
+            self.comment_type.tag().unwrap_or_default(),
+            self.max_age_days
+        )
+    }
+
+    /// Returns the sections that break the policy, i.e. are of the policy's type and older than
+    /// its max age. Sections without blame info have no known age and never break it.
+    pub fn find_violations<'a>(
+        &self,
+        sections: &'a [MarkedSection],
+        now: i64,
+    ) -> Vec<&'a MarkedSection> {
+        sections
+            .iter()
+            .filter(|x| x.comment_type == self.comment_type)
+            .filter(|x| x.age_days(now).is_some_and(|age| age > self.max_age_days))
+            .collect()
+    }
+}
+
+/// Keeps only the sections that are at least `min_age_days` old
+pub fn retain_older_than(sections: &mut Vec<MarkedSection>, min_age_days: i64, now: i64) {
+    sections.retain(|x| x.age_days(now).is_some_and(|age| age >= min_age_days));
+}
+
+/// Sorts the sections oldest first, sections with no known age go last
+pub fn sort_by_age(sections: &mut [MarkedSection], now: i64) {
+    sections.sort_by_key(|x| std::cmp::Reverse(x.age_days(now).unwrap_or(-1)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common_structs::{BlameInfo, Line};
+    use crate::dates::SECONDS_PER_DAY;
+    use crate::language::Language;
+
+    const NOW: i64 = 1_000 * SECONDS_PER_DAY;
+
+    fn section(comment_type: CommentType, age_days: Option<i64>) -> MarkedSection {
+        MarkedSection {
+            lines: vec![Line::new("// breadcrumb", 1, true)],
+            comment_type,
+            title_xxx: "lib.rs".to_string(),
+            language: Language::Rust,
+            embedded: false,
+            cell: None,
+            blame: age_days.map(|x| BlameInfo {
+                author: "Alice".to_string(),
+                email: "alice@example.com".to_string(),
+                commit: "0123456789abcdef".to_string(),
+                timestamp: NOW - x * SECONDS_PER_DAY,
+            }),
+            owners: vec![],
+        }
+    }
+
+    fn ages_of(sections: &[MarkedSection]) -> Vec<Option<i64>> {
+        sections.iter().map(|x| x.age_days(NOW)).collect()
+    }
+
+    #[test]
+    fn only_old_sections_of_policy_type_violate() {
+        let sections = vec![
+            section(CommentType::Fixme, Some(200)),
+            section(CommentType::Fixme, Some(10)),
+            section(CommentType::Todo, Some(500)),
+            section(CommentType::Fixme, None),
+        ];
+        let policy = StalePolicy {
+            comment_type: CommentType::Fixme,
+            max_age_days: 90,
+        };
+
+        let violations = policy.find_violations(&sections, NOW);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].age_days(NOW), Some(200));
+    }
+
+    #[test]
+    fn older_than_filter_drops_young_and_unknown_ages() {
+        let mut sections = vec![
+            section(CommentType::Todo, Some(200)),
+            section(CommentType::Todo, Some(10)),
+            section(CommentType::Todo, None),
+            section(CommentType::Todo, Some(180)),
+        ];
+        retain_older_than(&mut sections, 180, NOW);
+        assert_eq!(ages_of(&sections), vec![Some(200), Some(180)]);
+    }
+
+    #[test]
+    fn sorted_oldest_first_with_unknown_ages_last() {
+        let mut sections = vec![
+            section(CommentType::Todo, None),
+            section(CommentType::Todo, Some(10)),
+            section(CommentType::Todo, Some(300)),
+        ];
+        sort_by_age(&mut sections, NOW);
+        assert_eq!(ages_of(&sections), vec![Some(300), Some(10), None]);
+    }
+}
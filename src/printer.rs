@@ -1,7 +1,15 @@
-use super::common_structs::{CommentType, Line, MarkedSection};
-use ansi_term::Colour;
+use super::codeowners;
+use super::common_structs::{BlameInfo, CommentType, Line, MarkedSection};
+use super::dates;
+use super::diff::DiffReport;
+use super::history::HistoryReport;
+use super::hotspot::Hotspot;
+use super::render::{Renderer, Report};
+use ansi_term::{Colour, Style};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
 
-fn print_separator() {
+fn print_separator(out: &mut dyn Write) -> io::Result<()> {
     let separator = {
         let mut _str = String::new();
         let sep_char = '-';
@@ -10,44 +18,257 @@ fn print_separator() {
         }
         _str
     };
-    println!("{}", separator);
+    writeln!(out, "{}", separator)
 }
 
 pub struct ConsolePrinter {
-    special_colour: Colour,
+    special_style: Style,
 }
 
 impl ConsolePrinter {
     pub fn new(special_colour: Colour) -> ConsolePrinter {
-        ConsolePrinter { special_colour }
+        ConsolePrinter {
+            special_style: special_colour.normal(),
+        }
     }
 
-    pub fn print_all_lines(&self, code_patches: &Vec<MarkedSection>, display_type: CommentType) {
+    /// A printer that doesn't colour the special lines, for output written to a file
+    pub fn plain() -> ConsolePrinter {
+        ConsolePrinter {
+            special_style: Style::new(),
+        }
+    }
+
+    pub fn print_all_lines(
+        &self,
+        code_patches: &[MarkedSection],
+        display_type: CommentType,
+        out: &mut dyn Write,
+    ) -> io::Result<()> {
         let display_all = display_type == CommentType::Other;
+        let now = dates::now_timestamp();
 
         for patch in code_patches {
-            if !display_all {
-                if patch.comment_type != display_type {
-                    continue;
-                }
+            if !display_all && patch.comment_type != display_type {
+                continue;
+            }
+            print_separator(out)?;
+            if patch.embedded {
+                writeln!(out, "{} ({})", patch.location(), patch.language)?;
+            } else {
+                writeln!(out, "{}", patch.location())?;
+            }
+            self.print_section_body(patch, now, out)?;
+        }
+        print_separator(out)
+    }
+
+    /// Prints the added, removed and moved special lines of a [`DiffReport`](DiffReport),
+    /// each under its own heading and with its location, since they span many files
+    pub fn print_diff(
+        &self,
+        report: &DiffReport,
+        display_type: CommentType,
+        out: &mut dyn Write,
+    ) -> io::Result<()> {
+        let display_all = display_type == CommentType::Other;
+        let now = dates::now_timestamp();
+        let is_displayed = |x: &MarkedSection| display_all || x.comment_type == display_type;
+
+        let groups = [
+            ("Added", Colour::Green, &report.added),
+            ("Removed", Colour::Red, &report.removed),
+        ];
+        for (title, colour, sections) in groups.iter() {
+            let sections = sections
+                .iter()
+                .filter(|x| is_displayed(x))
+                .collect::<Vec<_>>();
+            writeln!(
+                out,
+                "{}",
+                colour
+                    .bold()
+                    .paint(format!("{} ({})", title, sections.len()))
+            )?;
+            for patch in sections {
+                print_separator(out)?;
+                writeln!(out, "{}", patch.location())?;
+                self.print_section_body(patch, now, out)?;
             }
-            print_separator();
-            for line in &patch.lines {
-                self.print_line(line);
+            print_separator(out)?;
+        }
+
+        let moved = report
+            .moved
+            .iter()
+            .filter(|x| is_displayed(&x.section))
+            .collect::<Vec<_>>();
+        writeln!(
+            out,
+            "{}",
+            Colour::Yellow
+                .bold()
+                .paint(format!("Moved ({})", moved.len()))
+        )?;
+        for moved_section in moved {
+            print_separator(out)?;
+            writeln!(
+                out,
+                "{} -> {}",
+                moved_section.from,
+                moved_section.section.location()
+            )?;
+            self.print_section_body(&moved_section.section, now, out)?;
+        }
+        print_separator(out)
+    }
+
+    /// Prints the lifecycle of each special line in a [`HistoryReport`](HistoryReport),
+    /// followed by the introduced and resolved counts per month
+    pub fn print_history(
+        &self,
+        report: &HistoryReport,
+        display_type: CommentType,
+        out: &mut dyn Write,
+    ) -> io::Result<()> {
+        let display_all = display_type == CommentType::Other;
+        let describe = |commit: &Option<BlameInfo>, missing: &str| match commit {
+            Some(commit) => format!(
+                "{} by {} ({})",
+                commit.date(),
+                commit.author,
+                commit.short_commit()
+            ),
+            None => missing.to_string(),
+        };
+
+        for lifecycle in &report.lifecycles {
+            if !display_all && lifecycle.comment_type != display_type {
+                continue;
             }
+            print_separator(out)?;
+            writeln!(out, "{}", lifecycle.path)?;
+            writeln!(out, "{}", self.special_style.paint(&lifecycle.text))?;
+            writeln!(
+                out,
+                "introduced: {}",
+                describe(&lifecycle.introduced, "before the walked range")
+            )?;
+            writeln!(
+                out,
+                "resolved:   {}",
+                describe(&lifecycle.resolved, "still open")
+            )?;
         }
-        print_separator();
+        print_separator(out)?;
+
+        writeln!(out, "{:<10}{:>12}{:>12}", "month", "introduced", "resolved")?;
+        for (month, stats) in report.monthly_stats() {
+            writeln!(
+                out,
+                "{:<10}{:>12}{:>12}",
+                month, stats.introduced, stats.resolved
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Prints the ranked [`Hotspot`](Hotspot) files, hottest first
+    pub fn print_hotspots(
+        &self,
+        hotspots: &[Hotspot],
+        now: i64,
+        out: &mut dyn Write,
+    ) -> io::Result<()> {
+        writeln!(
+            out,
+            "{:>8}{:>13}{:>9}{:>14}  file",
+            "score", "breadcrumbs", "commits", "last changed"
+        )?;
+        for hotspot in hotspots {
+            let last_changed = match hotspot.last_changed {
+                Some(timestamp) => format!("{}d ago", dates::days_since(timestamp, now)),
+                None => "uncommitted".to_string(),
+            };
+            writeln!(
+                out,
+                "{:>8.2}{:>13}{:>9}{:>14}  {}",
+                hotspot.score,
+                hotspot.breadcrumbs,
+                hotspot.commits,
+                last_changed,
+                self.special_style.paint(&hotspot.path)
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Prints how many special lines each owner has, see [`summarize`](crate::codeowners::summarize)
+    pub fn print_owner_summary(
+        &self,
+        summary: &BTreeMap<String, usize>,
+        out: &mut dyn Write,
+    ) -> io::Result<()> {
+        writeln!(out, "{:>12}  owner", "breadcrumbs")?;
+        for (owner, count) in summary {
+            writeln!(out, "{:>12}  {}", count, self.special_style.paint(owner))?;
+        }
+        Ok(())
+    }
+
+    /// Prints the blame info and owners (if any) and the lines of a single section
+    fn print_section_body(
+        &self,
+        patch: &MarkedSection,
+        now: i64,
+        out: &mut dyn Write,
+    ) -> io::Result<()> {
+        if let Some(blame) = &patch.blame {
+            writeln!(
+                out,
+                "{} <{}> {} {} ({} days old)",
+                blame.author,
+                blame.email,
+                blame.short_commit(),
+                blame.date(),
+                blame.age_days(now)
+            )?;
+        }
+        if !patch.owners.is_empty() {
+            writeln!(out, "owners: {}", patch.owners.join(" "))?;
+        }
+        for line in &patch.lines {
+            self.print_line(line, out)?;
+        }
+        Ok(())
     }
 
-    fn print_line(&self, line: &Line) {
+    fn print_line(&self, line: &Line, out: &mut dyn Write) -> io::Result<()> {
         if line.is_special {
-            println!(
+            writeln!(
+                out,
                 "{}\t{}",
                 &line.number,
-                self.special_colour.paint(&line.content).to_string()
-            );
+                self.special_style.paint(&line.content)
+            )
         } else {
-            println!("{}\t{}", &line.number, &line.content);
+            writeln!(out, "{}\t{}", &line.number, &line.content)
+        }
+    }
+}
+
+/// The terminal output: every displayed section, followed by the hotspots and the
+/// breadcrumbs per owner when they were asked for
+impl Renderer for ConsolePrinter {
+    fn render(&self, report: &Report, out: &mut dyn Write) -> io::Result<()> {
+        self.print_all_lines(report.sections, report.display_type.clone(), out)?;
+        if let Some(hotspots) = report.hotspots {
+            self.print_hotspots(hotspots, report.now, out)?;
+        }
+        if report.show_owners {
+            self.print_owner_summary(&codeowners::summarize(report.displayed()), out)?;
         }
+        Ok(())
     }
 }
@@ -0,0 +1,79 @@
+use super::common_structs::{CommentType, MarkedSection};
+use super::git::TreeSource;
+use super::hotspot::Hotspot;
+use std::io::{self, Write};
+
+/// What a scan found, as handed to each [`Renderer`](Renderer)
+pub struct Report<'a> {
+    /// Every special line found, in the order they're shown
+    pub sections: &'a [MarkedSection],
+    /// The type picked with `--type`, [`CommentType::Other`](CommentType::Other) for all of them
+    pub display_type: CommentType,
+    /// The scanned path
+    pub root: &'a str,
+    /// Unix timestamp the ages of the special lines are relative to
+    pub now: i64,
+    /// Files ranked by `--rank hotspot`, hottest first
+    pub hotspots: Option<&'a [Hotspot]>,
+    /// Set if the owners of the special lines were looked up
+    pub show_owners: bool,
+    /// The tree the files were read from, `None` for the working directory
+    pub tree_source: Option<&'a TreeSource>,
+}
+
+impl<'a> Report<'a> {
+    /// The special lines of the type picked with `--type`
+    pub fn displayed(&self) -> impl Iterator<Item = &'a MarkedSection> + Clone + '_ {
+        self.sections
+            .iter()
+            .filter(move |x| x.comment_type.is_displayed(&self.display_type))
+    }
+}
+
+/// Writes a [`Report`](Report) in a single output format
+///
+/// # Notes
+///
+/// Line based formats (see [`is_line_based`](crate::cli::OutputFormat::is_line_based)) are
+/// given a report per scanned file as soon as it's scanned, so their output for a whole scan
+/// must be the same as the outputs for each of its files one after the other.
+pub trait Renderer {
+    fn render(&self, report: &Report, out: &mut dyn Write) -> io::Result<()>;
+}
+
+/// Writes each of `lines` on its own line, for line based formats
+pub fn write_lines(out: &mut dyn Write, lines: impl Iterator<Item = String>) -> io::Result<()> {
+    for line in lines {
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::fixtures::section;
+
+    #[test]
+    fn only_picked_type_displayed() {
+        let mut fixme = section();
+        fixme.comment_type = CommentType::Fixme;
+        let sections = [section(), fixme];
+        let report = |display_type| Report {
+            sections: &sections,
+            display_type,
+            root: ".",
+            now: 0,
+            hotspots: None,
+            show_owners: false,
+            tree_source: None,
+        };
+
+        assert_eq!(report(CommentType::Other).displayed().count(), 2);
+        let fixmes = report(CommentType::Fixme);
+        assert!(fixmes
+            .displayed()
+            .all(|x| x.comment_type == CommentType::Fixme));
+        assert_eq!(fixmes.displayed().count(), 1);
+    }
+}
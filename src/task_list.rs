@@ -0,0 +1,131 @@
+use super::language::Language;
+
+/// Checks if `line` is a task list item, in the languages that have them:
+///
+/// * Markdown: `- [ ] item` (also `*`, `+` and numbered list markers)
+/// * Org-mode: `- [ ] item` and `* TODO heading`
+///
+/// Checked items (`- [x] item`, `* DONE heading`) only count if `include_checked` is set.
+pub fn is_task_item(line: &str, language: Language, include_checked: bool) -> bool {
+    let item = match language {
+        Language::Markdown => checkbox(line),
+        Language::Org => checkbox(line).or_else(|| org_heading(line)),
+        _ => None,
+    };
+    match item {
+        Some((is_checked, _)) => include_checked || !is_checked,
+        None => false,
+    }
+}
+
+/// The text of a task list item, without its list marker and checkbox or its heading stars and
+/// keyword, `None` if `line` isn't a task item
+pub fn item_text(line: &str) -> Option<&str> {
+    checkbox(line)
+        .or_else(|| org_heading(line))
+        .map(|(_, text)| text)
+}
+
+/// Returns whether the list item's checkbox is checked and the text after it, or `None` if
+/// `line` isn't a checkbox item
+fn checkbox(line: &str) -> Option<(bool, &str)> {
+    let item = strip_list_marker(line.trim_start())?;
+    let state = item.strip_prefix('[')?.chars().next()?;
+    let text = item[1 + state.len_utf8()..].strip_prefix(']')?;
+    match state {
+        ' ' => Some((false, text.trim())),
+        'x' | 'X' => Some((true, text.trim())),
+        _ => None,
+    }
+}
+
+fn strip_list_marker(line: &str) -> Option<&str> {
+    if let Some(rest) = ["- ", "* ", "+ "].iter().find_map(|x| line.strip_prefix(x)) {
+        return Some(rest);
+    }
+    let digits = line.chars().take_while(|x| x.is_ascii_digit()).count();
+    if digits == 0 {
+        return None;
+    }
+    let rest = &line[digits..];
+    rest.strip_prefix(". ").or_else(|| rest.strip_prefix(") "))
+}
+
+/// Org headings (`*`, `**`, ...) with a `TODO` or `DONE` keyword, as whether they're done and
+/// the text after the keyword
+///
+/// The stars have to be followed by a space, `*TODO` isn't a heading.
+fn org_heading(line: &str) -> Option<(bool, &str)> {
+    let heading = line.trim_start_matches('*');
+    if heading.len() == line.len() {
+        return None;
+    }
+    let heading = heading.strip_prefix(' ')?.trim_start();
+    let (keyword, text) = heading
+        .split_once(char::is_whitespace)
+        .unwrap_or((heading, ""));
+    match keyword {
+        "TODO" => Some((false, text.trim())),
+        "DONE" => Some((true, text.trim())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unchecked_markdown_items_detected() {
+        for line in &[
+            "- [ ] write docs",
+            "  * [ ] nested",
+            "+ [ ] plus",
+            "12. [ ] numbered",
+        ] {
+            assert!(is_task_item(line, Language::Markdown, false), "{}", line);
+        }
+    }
+
+    #[test]
+    fn checked_items_only_detected_when_included() {
+        assert!(!is_task_item("- [x] done", Language::Markdown, false));
+        assert!(is_task_item("- [X] done", Language::Markdown, true));
+        assert!(!is_task_item("* DONE shipped", Language::Org, false));
+        assert!(is_task_item("* DONE shipped", Language::Org, true));
+    }
+
+    #[test]
+    fn non_task_lines_ignored() {
+        for line in &[
+            "- plain item",
+            "[ ] no marker",
+            "- [link](url)",
+            "-[ ] no space",
+        ] {
+            assert!(!is_task_item(line, Language::Markdown, true), "{}", line);
+        }
+        assert!(!is_task_item("- [ ] in code", Language::Rust, true));
+    }
+
+    #[test]
+    fn org_todo_headings_detected() {
+        assert!(is_task_item("** TODO refile notes", Language::Org, false));
+        assert!(!is_task_item("* TODOS are headings", Language::Org, false));
+        assert!(!is_task_item("*TODO not a heading", Language::Org, false));
+        assert!(!is_task_item(
+            "* TODO in markdown",
+            Language::Markdown,
+            false
+        ));
+    }
+
+    #[test]
+    fn item_text_without_markers() {
+        assert_eq!(item_text("- [ ] write docs"), Some("write docs"));
+        assert_eq!(item_text("  * [x] shipped"), Some("shipped"));
+        assert_eq!(item_text("1. [ ] first"), Some("first"));
+        assert_eq!(item_text("** DONE refile notes"), Some("refile notes"));
+        assert_eq!(item_text("- plain item"), None);
+    }
+}
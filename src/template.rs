@@ -0,0 +1,565 @@
+use super::dates;
+use super::formats::{json, xml};
+use super::render::{Renderer, Report};
+use serde_json::{json, Map, Value};
+use std::fmt;
+use std::io::{self, Write};
+
+/// Filters that can follow a value, e.g. `{{ message | html }}`
+const FILTERS: [&str; 9] = [
+    "upper", "lower", "trim", "length", "html", "xml", "json", "url", "md",
+];
+
+/// Characters with a meaning in Markdown, escaped by the `md` filter
+const MARKDOWN_SPECIAL: [char; 16] = [
+    '\\', '`', '*', '_', '{', '}', '[', ']', '<', '>', '(', ')', '#', '+', '!', '|',
+];
+
+/// A problem found while parsing a template
+#[derive(Debug)]
+pub struct TemplateError {
+    /// 1-based line of the template the problem is on
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// A value to look up, with the filters to run it through
+struct Expression {
+    path: Vec<String>,
+    filters: Vec<String>,
+}
+
+struct Condition {
+    path: Vec<String>,
+    negated: bool,
+    /// Whether the value has to be equal (or not) to a string literal, rather than just be set
+    comparison: Option<(bool, String)>,
+}
+
+enum Node {
+    Text(String),
+    Output(Expression),
+    For {
+        name: String,
+        path: Vec<String>,
+        body: Vec<Node>,
+    },
+    If {
+        condition: Condition,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+}
+
+enum Token {
+    Text(String),
+    /// The inside of `{{ ... }}`, with its line
+    Output(String, usize),
+    /// The inside of `{% ... %}`, with its line
+    Tag(String, usize),
+}
+
+/// A user-defined output format, see `--template`
+///
+/// # Syntax
+///
+/// * `{{ path.to.value | filter }}` writes a value, run through any number of filters
+///   (`upper`, `lower`, `trim`, `length`, and the escaping `html`, `xml`, `json`, `url` and `md`)
+/// * `{% for x in path %}...{% endfor %}` repeats its body for each item of a list, with
+///   `loop.index`, `loop.first` and `loop.last` set
+/// * `{% if [not] path [== "text"] %}...{% else %}...{% endif %}` checks if a value is set
+///   (not null, false, empty or zero) or compares it to a string
+///
+/// Tags alone on their line take the whole line with them, so they don't leave blank lines.
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Self, TemplateError> {
+        let tokens = tokenize(source)?;
+        let (nodes, _) = parse_nodes(&tokens, &mut 0, &[])?;
+        Ok(Self { nodes })
+    }
+
+    /// Renders the template with `context` as the root of all the paths
+    pub fn render_value(&self, context: &Value) -> String {
+        let mut output = String::new();
+        render_nodes(&self.nodes, context, &mut vec![], &mut output);
+        output
+    }
+}
+
+/// Renders the displayed special lines, grouped by file too, see [`context`](context)
+impl Renderer for Template {
+    fn render(&self, report: &Report, out: &mut dyn Write) -> io::Result<()> {
+        write!(out, "{}", self.render_value(&context(report)))
+    }
+}
+
+/// The values a template can use: the `root` scanned, today's `date`, the `breadcrumbs`, the
+/// `files` with their `path` and `breadcrumbs`, and the `count` of breadcrumbs with `counts`
+/// per type
+///
+/// Each breadcrumb is the same object as in the `--format json` report, see `schema/`.
+fn context(report: &Report) -> Value {
+    let breadcrumbs = report
+        .displayed()
+        .map(json::breadcrumb)
+        .collect::<Vec<Value>>();
+
+    let mut files: Vec<(String, Vec<Value>)> = vec![];
+    let mut counts = Map::new();
+    for breadcrumb in &breadcrumbs {
+        let path = breadcrumb["file"].as_str().unwrap_or_default();
+        match files.iter_mut().find(|x| x.0 == path) {
+            Some((_, file_breadcrumbs)) => file_breadcrumbs.push(breadcrumb.clone()),
+            None => files.push((path.to_string(), vec![breadcrumb.clone()])),
+        }
+        if let Some(tag) = breadcrumb["type"].as_str() {
+            let count = counts.get(tag).and_then(|x| x.as_u64()).unwrap_or(0);
+            counts.insert(tag.to_string(), json!(count + 1));
+        }
+    }
+
+    json!({
+        "root": report.root,
+        "date": dates::format_date(report.now),
+        "count": breadcrumbs.len(),
+        "counts": counts,
+        "files": files
+            .into_iter()
+            .map(|(path, breadcrumbs)| json!({
+                "path": path,
+                "count": breadcrumbs.len(),
+                "breadcrumbs": breadcrumbs,
+            }))
+            .collect::<Vec<Value>>(),
+        "breadcrumbs": breadcrumbs,
+    })
+}
+
+/// Splits the template into text, values and tags
+///
+/// A tag alone on its line (but for whitespace) takes the whole line, newline included.
+fn tokenize(source: &str) -> Result<Vec<Token>, TemplateError> {
+    let mut tokens = vec![];
+    let mut position = 0;
+
+    while let Some(offset) = source[position..].find('{') {
+        let start = position + offset;
+        let rest = &source[start..];
+        let closer = if rest.starts_with("{{") {
+            "}}"
+        } else if rest.starts_with("{%") {
+            "%}"
+        } else {
+            tokens.push(Token::Text(source[position..start + 1].to_string()));
+            position = start + 1;
+            continue;
+        };
+        let line = source[..start].matches('\n').count() + 1;
+        // searching past the opener, so that `{%}` isn't taken as opened and closed
+        let end = match rest[2..].find(closer) {
+            Some(end) => start + 2 + end + closer.len(),
+            None => {
+                return Err(TemplateError {
+                    line,
+                    message: format!("`{}` is never closed with `{}`", &rest[..2], closer),
+                })
+            }
+        };
+        let inside = source[start + 2..end - 2].trim().to_string();
+
+        let mut text_end = start;
+        let mut next = end;
+        if closer == "%}" {
+            let line_start = source[..start].rfind('\n').map_or(0, |x| x + 1);
+            let line_end = source[end..].find('\n').map_or(source.len(), |x| end + x);
+            if source[line_start..start].trim().is_empty()
+                && source[end..line_end].trim().is_empty()
+            {
+                text_end = line_start.max(position);
+                next = (line_end + 1).min(source.len());
+            }
+        }
+        if text_end > position {
+            tokens.push(Token::Text(source[position..text_end].to_string()));
+        }
+        tokens.push(if closer == "}}" {
+            Token::Output(inside, line)
+        } else {
+            Token::Tag(inside, line)
+        });
+        position = next;
+    }
+    if position < source.len() {
+        tokens.push(Token::Text(source[position..].to_string()));
+    }
+    Ok(tokens)
+}
+
+/// Parses nodes up to one of the `terminators` tags, which is returned, or the end of the tokens
+fn parse_nodes(
+    tokens: &[Token],
+    position: &mut usize,
+    terminators: &[&str],
+) -> Result<(Vec<Node>, Option<String>), TemplateError> {
+    let mut nodes = vec![];
+
+    while let Some(token) = tokens.get(*position) {
+        *position += 1;
+        let (tag, line) = match token {
+            Token::Text(text) => {
+                nodes.push(Node::Text(text.clone()));
+                continue;
+            }
+            Token::Output(expression, line) => {
+                nodes.push(Node::Output(parse_expression(expression, *line)?));
+                continue;
+            }
+            Token::Tag(tag, line) => (tag, *line),
+        };
+
+        let mut words = tag.split_whitespace();
+        let keyword = words.next().unwrap_or_default();
+        if terminators.contains(&keyword) {
+            return Ok((nodes, Some(keyword.to_string())));
+        }
+        let error = |message: String| TemplateError { line, message };
+        match keyword {
+            "for" => {
+                let (name, path) = match (words.next(), words.next(), words.next(), words.next()) {
+                    (Some(name), Some("in"), Some(path), None) => (name, parse_path(path, line)?),
+                    _ => return Err(error(format!("`{}` should be `for x in path`", tag))),
+                };
+                let (body, end) = parse_nodes(tokens, position, &["endfor"])?;
+                if end.is_none() {
+                    return Err(error("`for` is never closed with `endfor`".to_string()));
+                }
+                nodes.push(Node::For {
+                    name: name.to_string(),
+                    path,
+                    body,
+                });
+            }
+            "if" => {
+                let condition = parse_condition(&tag[2..], line)?;
+                let (then, end) = parse_nodes(tokens, position, &["else", "endif"])?;
+                let otherwise = match end {
+                    Some(keyword) if keyword == "else" => {
+                        match parse_nodes(tokens, position, &["endif"])? {
+                            (otherwise, Some(_)) => otherwise,
+                            (_, None) => {
+                                return Err(error("`if` is never closed with `endif`".to_string()))
+                            }
+                        }
+                    }
+                    Some(_) => vec![],
+                    None => return Err(error("`if` is never closed with `endif`".to_string())),
+                };
+                nodes.push(Node::If {
+                    condition,
+                    then,
+                    otherwise,
+                });
+            }
+            "endfor" | "else" | "endif" => {
+                return Err(error(format!("`{}` without an opening tag", keyword)))
+            }
+            _ => return Err(error(format!("unknown tag `{}`", tag))),
+        }
+    }
+    Ok((nodes, None))
+}
+
+fn parse_path(path: &str, line: usize) -> Result<Vec<String>, TemplateError> {
+    let segments = path
+        .split('.')
+        .map(|x| x.to_string())
+        .collect::<Vec<String>>();
+    let is_valid = segments
+        .iter()
+        .all(|x| !x.is_empty() && x.chars().all(|x| x.is_alphanumeric() || x == '_'));
+    if is_valid {
+        Ok(segments)
+    } else {
+        Err(TemplateError {
+            line,
+            message: format!("`{}` isn't a path like `breadcrumb.message`", path),
+        })
+    }
+}
+
+fn parse_expression(expression: &str, line: usize) -> Result<Expression, TemplateError> {
+    let mut parts = expression.split('|').map(|x| x.trim());
+    let path = parse_path(parts.next().unwrap_or_default(), line)?;
+    let filters = parts
+        .map(|filter| {
+            if FILTERS.contains(&filter) {
+                Ok(filter.to_string())
+            } else {
+                Err(TemplateError {
+                    line,
+                    message: format!(
+                        "unknown filter `{}`, pick one of {}",
+                        filter,
+                        FILTERS.join(", ")
+                    ),
+                })
+            }
+        })
+        .collect::<Result<Vec<String>, TemplateError>>()?;
+    Ok(Expression { path, filters })
+}
+
+fn parse_condition(condition: &str, line: usize) -> Result<Condition, TemplateError> {
+    let condition = condition.trim();
+    let (negated, condition) = match condition.strip_prefix("not ") {
+        Some(rest) => (true, rest.trim()),
+        None => (false, condition),
+    };
+    let (path, comparison) = match condition.find(['=', '!']) {
+        Some(start) => {
+            let (path, rest) = condition.split_at(start);
+            let (is_equal, literal) = if let Some(literal) = rest.strip_prefix("==") {
+                (true, literal.trim())
+            } else if let Some(literal) = rest.strip_prefix("!=") {
+                (false, literal.trim())
+            } else {
+                (true, "")
+            };
+            match literal.strip_prefix('"').and_then(|x| x.strip_suffix('"')) {
+                Some(literal) => (path.trim(), Some((is_equal, literal.to_string()))),
+                _ => {
+                    return Err(TemplateError {
+                        line,
+                        message: format!(
+                            "`{}` should compare with a quoted string, e.g. `type == \"FIXME\"`",
+                            condition
+                        ),
+                    })
+                }
+            }
+        }
+        None => (condition, None),
+    };
+    Ok(Condition {
+        path: parse_path(path, line)?,
+        negated,
+        comparison,
+    })
+}
+
+fn render_nodes(
+    nodes: &[Node],
+    context: &Value,
+    scopes: &mut Vec<(String, Value)>,
+    output: &mut String,
+) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Output(expression) => {
+                let value = lookup(&expression.path, context, scopes);
+                let value = expression
+                    .filters
+                    .iter()
+                    .fold(value, |value, filter| apply_filter(filter, &value));
+                output.push_str(&to_text(&value));
+            }
+            Node::For { name, path, body } => {
+                let items = match lookup(path, context, scopes) {
+                    Value::Array(items) => items,
+                    _ => vec![],
+                };
+                for (i, item) in items.iter().enumerate() {
+                    let state = json!({
+                        "index": i + 1,
+                        "first": i == 0,
+                        "last": i + 1 == items.len(),
+                    });
+                    scopes.push(("loop".to_string(), state));
+                    scopes.push((name.clone(), item.clone()));
+                    render_nodes(body, context, scopes, output);
+                    scopes.truncate(scopes.len() - 2);
+                }
+            }
+            Node::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let value = lookup(&condition.path, context, scopes);
+                let is_met = match &condition.comparison {
+                    Some((is_equal, literal)) => (&to_text(&value) == literal) == *is_equal,
+                    None => is_truthy(&value),
+                };
+                let branch = if is_met != condition.negated {
+                    then
+                } else {
+                    otherwise
+                };
+                render_nodes(branch, context, scopes, output);
+            }
+        }
+    }
+}
+
+/// Looks up a path in the loop variables, innermost first, then in the context, `null` if it's missing
+fn lookup(path: &[String], context: &Value, scopes: &[(String, Value)]) -> Value {
+    let root = scopes
+        .iter()
+        .rev()
+        .find(|x| x.0 == path[0])
+        .map_or(&context[path[0].as_str()], |x| &x.1);
+    path[1..]
+        .iter()
+        .fold(root, |value, segment| match segment.parse::<usize>() {
+            Ok(index) if value.is_array() => &value[index],
+            _ => &value[segment.as_str()],
+        })
+        .clone()
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(x) => *x,
+        Value::Number(x) => x.as_f64() != Some(0.0),
+        Value::String(x) => !x.is_empty(),
+        Value::Array(x) => !x.is_empty(),
+        Value::Object(x) => !x.is_empty(),
+    }
+}
+
+/// How a value is written out, lists of values being joined with commas
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(x) => x.clone(),
+        Value::Array(items) => items
+            .iter()
+            .map(to_text)
+            .collect::<Vec<String>>()
+            .join(", "),
+        _ => value.to_string(),
+    }
+}
+
+fn apply_filter(filter: &str, value: &Value) -> Value {
+    let text = to_text(value);
+    let filtered = match filter {
+        "upper" => text.to_uppercase(),
+        "lower" => text.to_lowercase(),
+        "trim" => text.trim().to_string(),
+        "length" => {
+            return json!(match value {
+                Value::Array(items) => items.len(),
+                Value::Object(fields) => fields.len(),
+                _ => text.chars().count(),
+            })
+        }
+        "html" | "xml" => xml::escape(&text),
+        // quoted, ready to be put in a JSON document as is
+        "json" => value.to_string(),
+        "url" => text
+            .bytes()
+            .map(|x| match x {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                    (x as char).to_string()
+                }
+                _ => format!("%{:02X}", x),
+            })
+            .collect(),
+        "md" => text
+            .chars()
+            .flat_map(|x| {
+                let escape = MARKDOWN_SPECIAL.contains(&x).then_some('\\');
+                escape.into_iter().chain(std::iter::once(x))
+            })
+            .collect(),
+        _ => text,
+    };
+    Value::String(filtered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(template: &str, context: Value) -> String {
+        Template::parse(template).unwrap().render_value(&context)
+    }
+
+    #[test]
+    fn loops_conditionals_and_filters() {
+        let context = json!({
+            "files": [
+                { "path": "a.rs", "breadcrumbs": [
+                    { "type": "FIXME", "message": "<leak>", "metadata": { "owners": ["@a", "@b"] } },
+                    { "type": "TODO", "message": "docs & tests", "metadata": { "owners": [] } },
+                ]},
+            ],
+        });
+        let template = "\
+{% for file in files %}
+# {{ file.path | upper }}
+{% for crumb in file.breadcrumbs %}
+  {{ loop.index }}. {% if crumb.type == \"FIXME\" %}!{% endif %}{{ crumb.message | html }}\
+{% if crumb.metadata.owners %} ({{ crumb.metadata.owners }}){% else %} (unowned){% endif %}
+{% endfor %}
+{% endfor %}
+";
+
+        assert_eq!(
+            render(template, context),
+            "# A.RS\n  1. !&lt;leak&gt; (@a, @b)\n  2. docs &amp; tests (unowned)\n"
+        );
+    }
+
+    #[test]
+    fn escaping_filters() {
+        let context = json!({ "text": "a \"b\" [c]/d", "count": 0 });
+        assert_eq!(
+            render(
+                "{{ text | json }} {{ text | url }} {{ text | md }} {{ text | length }}",
+                context.clone()
+            ),
+            "\"a \\\"b\\\" [c]/d\" a%20%22b%22%20%5Bc%5D%2Fd a \"b\" \\[c\\]/d 11"
+        );
+        assert_eq!(
+            render(
+                "{% if not count %}none{% endif %}{{ missing.field }}",
+                context
+            ),
+            "none"
+        );
+    }
+
+    #[test]
+    fn parse_errors_have_lines() {
+        let error = |template| Template::parse(template).err().unwrap().to_string();
+        assert_eq!(
+            error("a\n{% for x in xs %}\n{{ x }}"),
+            "line 2: `for` is never closed with `endfor`"
+        );
+        assert_eq!(
+            error("{{ x | shout }}"),
+            "line 1: unknown filter `shout`, pick one of upper, lower, trim, length, html, xml, json, url, md"
+        );
+        assert_eq!(
+            error("\n\n{% endif %}"),
+            "line 3: `endif` without an opening tag"
+        );
+        assert_eq!(error("{{ x "), "line 1: `{{` is never closed with `}}`");
+        assert_eq!(error("x {%} y"), "line 1: `{%` is never closed with `%}`");
+        assert_eq!(error("{{}"), "line 1: `{{` is never closed with `}}`");
+    }
+}